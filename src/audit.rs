@@ -0,0 +1,179 @@
+//! An audit log of the errors that are tolerated.
+//!
+//! When an audit log is set, each error that is tolerated, such as a
+//! `NotFound` error converted to `Ok(None)` by
+//! [`optional`](crate::IoResultOptional::optional), is written as a
+//! line of JSON, with the time, the kind and message of the error, the
+//! path if it is known, and the location in the source where it was
+//! tolerated.
+//! The path is known for the helpers in [`fs`](crate::fs).
+//!
+//! Writing is done on a thread of its own, so tolerating an error
+//! never waits for the log.
+//! If the log can not keep up, records are dropped, and errors writing
+//! the log are ignored.
+//!
+//! This module is available with the `audit` feature.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::audit;
+//! use io_result_optional::IoResultOptional;
+//! use std::fs;
+//! # use std::io;
+//!
+//! # fn main() -> io::Result<()> {
+//! # let dir = tempfile::tempdir()?;
+//! # let log = dir.path().join("tolerated.jsonl");
+//! audit::set_file(&log)?;
+//! let config = fs::read_to_string(".app.rc").optional()?;
+//! # audit::clear();
+//! # Ok(())
+//! # }
+//! ````
+//! This writes a line like the following:
+//! ````text
+//! {"kind":"NotFound","location":"src/main.rs:6:44","message":"No such file or directory (os error 2)","path":null,"timestamp":1760000000.123}
+//! ````
+use std::cell::RefCell;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::RwLock;
+use std::thread;
+use std::time::SystemTime;
+
+/// The number of records that may wait to be written.
+const BUFFER: usize = 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SENDER: RwLock<Option<SyncSender<String>>> = RwLock::new(None);
+
+thread_local! {
+    static PATH: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Write the audit log to `writer`, replacing any previous log.
+///
+/// Each record is written as one line, and the writer is flushed when
+/// there are no more records waiting.
+pub fn set_writer<W: Write + Send + 'static>(mut writer: W) {
+    let (sender, records) = sync_channel::<String>(BUFFER);
+    thread::spawn(move || {
+        // Ends when the sender is dropped by clear or set_writer.
+        while let Ok(record) = records.recv() {
+            let _ = writer.write_all(record.as_bytes());
+            for record in records.try_iter() {
+                let _ = writer.write_all(record.as_bytes());
+            }
+            let _ = writer.flush();
+        }
+    });
+    *SENDER.write().unwrap_or_else(|e| e.into_inner()) = Some(sender);
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Append the audit log to the file at `path`, creating it if needed.
+pub fn set_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    set_writer(file);
+    Ok(())
+}
+
+/// Stop writing the audit log.
+///
+/// Records that are already waiting are still written.
+pub fn clear() {
+    ENABLED.store(false, Ordering::Release);
+    SENDER.write().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+/// Record that `error` is tolerated at `location`.
+///
+/// An error of another type than `io::Error` is given as `None`.
+pub(crate) fn record(error: Option<&io::Error>, location: &'static Location<'static>) {
+    if !ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0.0, |t| t.as_secs_f64());
+    let path = PATH.with(|path| path.borrow().as_ref().map(|p| p.display().to_string()));
+    let mut line = serde_json::json!({
+        "timestamp": timestamp,
+        "path": path,
+        "kind": error.map(|e| format!("{:?}", e.kind())),
+        "message": error.map(ToString::to_string),
+        "location": location.to_string(),
+    })
+    .to_string();
+    line.push('\n');
+    if let Some(sender) = &*SENDER.read().unwrap_or_else(|e| e.into_inner()) {
+        // Drop the record rather than wait if the log is behind.
+        let _ = sender.try_send(line);
+    }
+}
+
+/// Make `attempt`, with errors tolerated in it recorded as being about
+/// `path`.
+pub(crate) fn with_path<T>(path: &Path, attempt: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Acquire) {
+        return attempt();
+    }
+    let previous = PATH.with(|p| p.replace(Some(path.into())));
+    let result = attempt();
+    PATH.with(|p| *p.borrow_mut() = previous);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audited");
+        let log = Shared::default();
+        super::set_writer(log.clone());
+        let line = line!() + 1;
+        assert!(crate::fs::lines_optional(&path).unwrap().count() == 0);
+        let expected = format!("\"path\":{}", serde_json::json!(path.display().to_string()));
+        let mut found = None;
+        for _ in 0..500 {
+            let text = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+            found = text
+                .lines()
+                .find(|l| l.contains(&expected))
+                .map(String::from);
+            if found.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        super::clear();
+        let record: serde_json::Value = serde_json::from_str(&found.unwrap()).unwrap();
+        assert_eq!(record["kind"], "NotFound");
+        // The location is where the helper was called, not in the crate.
+        let location = record["location"].as_str().unwrap();
+        assert!(location.starts_with(&format!("src/audit.rs:{}:", line)));
+    }
+}
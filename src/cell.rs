@@ -0,0 +1,193 @@
+//! Cells for optional values that are loaded once, when needed.
+use std::cell::OnceCell;
+use std::fmt;
+use std::io;
+use std::sync::OnceLock;
+
+/// A cell for an optional value that is loaded the first time it is
+/// needed.
+///
+/// The outcome of the load is cached, whether it is a value, `None`
+/// or an error, so the load is not attempted again until
+/// [`try_reload`](Self::try_reload) is called.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::{IoResultOptional, OnceOptional};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let flags = OnceOptional::new(|| std::fs::read_to_string("features.txt").optional());
+/// // Later, any number of times, only reads the file the first time:
+/// let enabled = match flags.get() {
+///     Ok(Some(flags)) => flags.lines().any(|flag| flag == "fancy"),
+///     Ok(None) => false,
+///     Err(e) => return Err(io::Error::new(e.kind(), e.to_string())),
+/// };
+/// # Ok(())
+/// # }
+/// ````
+pub struct OnceOptional<T, F = fn() -> io::Result<Option<T>>> {
+    cell: OnceCell<io::Result<Option<T>>>,
+    load: F,
+}
+
+impl<T, F: Fn() -> io::Result<Option<T>>> OnceOptional<T, F> {
+    /// Create a new cell, that will get its value by calling `load`.
+    pub const fn new(load: F) -> Self {
+        OnceOptional {
+            cell: OnceCell::new(),
+            load,
+        }
+    }
+
+    /// Get the value, loading it if it has not been loaded before.
+    pub fn get(&self) -> Result<Option<&T>, &io::Error> {
+        self.cell
+            .get_or_init(&self.load)
+            .as_ref()
+            .map(Option::as_ref)
+    }
+
+    /// Load the value again, even if it has been loaded before, and
+    /// return the new outcome.
+    pub fn try_reload(&mut self) -> Result<Option<&T>, &io::Error> {
+        self.cell = OnceCell::new();
+        self.get()
+    }
+
+    /// Return true if the value has been loaded (successfully or not).
+    pub fn is_loaded(&self) -> bool {
+        self.cell.get().is_some()
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for OnceOptional<T, F> {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        out.debug_struct("OnceOptional")
+            .field("value", &self.cell.get())
+            .finish()
+    }
+}
+
+/// A value for a static, that may not exist, loaded the first time
+/// it is needed.
+///
+/// This is like [`OnceOptional`], but can be shared between threads,
+/// so it can be used for global configuration.
+///
+/// The outcome of the load is kept, whether it is a value, `None` or
+/// an error.
+/// After an error, [`get`](Self::get) panics with it each time it is
+/// called, while [`try_get`](Self::try_get) returns it.
+/// If `load` panics, the cell is left as it was, and the load is
+/// attempted again the next time the value is needed.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::{IoResultOptional, OptionalLazy};
+/// use std::fs;
+///
+/// static SITE_CONF: OptionalLazy<String> =
+///     OptionalLazy::new(|| fs::read_to_string("/etc/app/site.conf").optional());
+///
+/// if let Some(conf) = SITE_CONF.get() {
+///     println!("Using site configuration {:?}", conf);
+/// }
+/// ````
+pub struct OptionalLazy<T, F = fn() -> io::Result<Option<T>>> {
+    cell: OnceLock<io::Result<Option<T>>>,
+    load: F,
+}
+
+impl<T, F: Fn() -> io::Result<Option<T>>> OptionalLazy<T, F> {
+    /// Create a new cell, that will get its value by calling `load`.
+    pub const fn new(load: F) -> Self {
+        OptionalLazy {
+            cell: OnceLock::new(),
+            load,
+        }
+    }
+
+    /// Get the value, loading it if it has not been loaded before.
+    ///
+    /// # Panics
+    ///
+    /// If loading the value failed, this panics with the error.
+    #[track_caller]
+    pub fn get(&self) -> &Option<T> {
+        match self.try_get() {
+            Ok(value) => value,
+            Err(e) => panic!("failed to load optional value: {}", e),
+        }
+    }
+
+    /// Get the value, or the error loading it, loading it if it has
+    /// not been loaded before.
+    pub fn try_get(&self) -> Result<&Option<T>, &io::Error> {
+        self.cell.get_or_init(&self.load).as_ref()
+    }
+
+    /// Return true if the value has been loaded (successfully or not).
+    pub fn is_loaded(&self) -> bool {
+        self.cell.get().is_some()
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for OptionalLazy<T, F> {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        out.debug_struct("OptionalLazy")
+            .field("value", &self.cell.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OnceOptional, OptionalLazy};
+    use std::cell::Cell;
+    use std::io;
+
+    #[test]
+    fn loads_once() {
+        let calls = Cell::new(0);
+        let mut cell = OnceOptional::new(|| {
+            calls.set(calls.get() + 1);
+            Ok(Some(calls.get()))
+        });
+        assert!(!cell.is_loaded());
+        assert_eq!(cell.get().unwrap(), Some(&1));
+        assert_eq!(cell.get().unwrap(), Some(&1));
+        assert_eq!(cell.try_reload().unwrap(), Some(&2));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn caches_errors() {
+        let calls = Cell::new(0);
+        let cell = OnceOptional::new(|| -> io::Result<Option<()>> {
+            calls.set(calls.get() + 1);
+            Err(io::ErrorKind::PermissionDenied.into())
+        });
+        assert_eq!(
+            cell.get().unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        assert!(cell.get().is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn lazy_static() {
+        static VALUE: OptionalLazy<u8> = OptionalLazy::new(|| Ok(Some(7)));
+        static MISSING: OptionalLazy<u8> = OptionalLazy::new(|| Ok(None));
+        static FAILED: OptionalLazy<u8> =
+            OptionalLazy::new(|| Err(io::ErrorKind::PermissionDenied.into()));
+        assert!(!VALUE.is_loaded());
+        assert_eq!(std::thread::spawn(|| *VALUE.get()).join().unwrap(), Some(7));
+        assert!(VALUE.is_loaded());
+        assert_eq!(MISSING.get(), &None);
+        assert!(FAILED.try_get().is_err());
+        assert!(std::panic::catch_unwind(|| FAILED.get()).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Optional input files as command line arguments.
+//!
+//! This module is available with the `clap` feature.
+use crate::{EmptyPathError, OptionalInput};
+use ::clap::builder::{TypedValueParser, ValueParserFactory};
+use ::clap::error::ErrorKind;
+use ::clap::{Arg, Command};
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+/// A command line argument naming an input file that may not exist,
+/// or `-` for standard input.
+///
+/// This is an [`OptionalInput`] that is validated when parsed by clap:
+/// it must not be empty, and if the path exists it must not be a
+/// directory.
+///
+/// # Examples
+/// ````
+/// use clap::{Arg, Command};
+/// use io_result_optional::clap::OptionalPathArg;
+/// # use std::io::{self, Read};
+///
+/// # fn main() -> io::Result<()> {
+/// let matches = Command::new("app")
+///     .arg(Arg::new("extra").value_parser(clap::value_parser!(OptionalPathArg)))
+///     .get_matches_from(["app", "nosuch.txt"]);
+/// let extra = matches.get_one::<OptionalPathArg>("extra").unwrap();
+/// if let Some(mut input) = extra.open()? {
+///     let mut text = String::new();
+///     input.read_to_string(&mut text)?;
+///     // Handle the extra input ...
+/// }
+/// # Ok(())
+/// # }
+/// ````
+pub type OptionalPathArg = OptionalInput;
+
+impl ValueParserFactory for OptionalInput {
+    type Parser = OptionalPathArgParser;
+
+    fn value_parser() -> Self::Parser {
+        OptionalPathArgParser
+    }
+}
+
+/// The clap value parser for [`OptionalPathArg`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OptionalPathArgParser;
+
+impl TypedValueParser for OptionalPathArgParser {
+    type Value = OptionalInput;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<OptionalInput, ::clap::Error> {
+        let fail = |msg: String| {
+            let name = arg.map_or_else(|| "...".into(), |arg| arg.get_id().to_string());
+            ::clap::Error::raw(
+                ErrorKind::InvalidValue,
+                format!("invalid value for <{}>: {}\n", name, msg),
+            )
+            .with_cmd(cmd)
+        };
+        if value.is_empty() {
+            Err(fail(EmptyPathError.to_string()))
+        } else if value == "-" {
+            Ok(OptionalInput::Stdin)
+        } else {
+            let path = PathBuf::from(value);
+            if path.is_dir() {
+                Err(fail(format!("{} is a directory", path.display())))
+            } else {
+                Ok(OptionalInput::Path(path))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OptionalPathArg;
+    use ::clap::{value_parser, Arg, Command};
+    use std::path::Path;
+
+    fn parse(value: &str) -> Result<OptionalPathArg, ::clap::Error> {
+        Command::new("test")
+            .arg(Arg::new("input").value_parser(value_parser!(OptionalPathArg)))
+            .try_get_matches_from(["test", value])
+            .map(|matches| matches.get_one::<OptionalPathArg>("input").unwrap().clone())
+    }
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(parse("-").unwrap(), OptionalPathArg::Stdin);
+        let arg = parse("nosuch.file").unwrap();
+        assert_eq!(arg.path(), Some(Path::new("nosuch.file")));
+        assert!(arg.open().unwrap().is_none());
+        assert!(parse("").is_err());
+        assert!(parse(env!("CARGO_MANIFEST_DIR")).is_err());
+    }
+}
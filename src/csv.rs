@@ -0,0 +1,107 @@
+//! Reading optional CSV files.
+//!
+//! This module is available with the `csv` feature.
+use crate::IoResultOptional;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Open a CSV file that may not exist.
+///
+/// If the file does not exist, `Ok(None)` is returned.
+/// Otherwise, a [`csv::Reader`] configured by `builder` is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::csv::{read_csv_optional, CsvError};
+///
+/// # fn main() -> Result<(), CsvError> {
+/// let builder = csv::ReaderBuilder::new();
+/// if let Some(mut reader) = read_csv_optional("extra.csv", &builder)? {
+///     for record in reader.records() {
+///         let record = record?;
+///         // Handle the record ...
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ````
+pub fn read_csv_optional<P: AsRef<Path>>(
+    path: P,
+    builder: &::csv::ReaderBuilder,
+) -> Result<Option<::csv::Reader<File>>, CsvError> {
+    Ok(File::open(path)
+        .optional()?
+        .map(|file| builder.from_reader(file)))
+}
+
+/// An error reading a CSV file, either from io or from parsing.
+#[derive(Debug)]
+pub enum CsvError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// The file was read, but its content could not be parsed.
+    Parse(::csv::Error),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsvError::Io(e) => e.fmt(out),
+            CsvError::Parse(e) => e.fmt(out),
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io(e) => Some(e),
+            CsvError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(e: io::Error) -> CsvError {
+        CsvError::Io(e)
+    }
+}
+
+/// A [`csv::Error`] caused by io is converted to [`CsvError::Io`],
+/// all others to [`CsvError::Parse`].
+impl From<::csv::Error> for CsvError {
+    fn from(e: ::csv::Error) -> CsvError {
+        if !e.is_io_error() {
+            return CsvError::Parse(e);
+        }
+        match e.into_kind() {
+            ::csv::ErrorKind::Io(e) => CsvError::Io(e),
+            // is_io_error should mean the Io kind, but keep anything
+            // else as an io error rather than panic.
+            kind => CsvError::Io(io::Error::other(format!("{:?}", kind))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_csv_optional, CsvError};
+    use std::path::Path;
+
+    #[test]
+    fn non_existing_none() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("nosuch.csv");
+        let builder = ::csv::ReaderBuilder::new();
+        assert!(read_csv_optional(path, &builder).unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_error_is_parse() {
+        let mut reader = ::csv::ReaderBuilder::new().from_reader(&b"a,b\n1,2,3\n"[..]);
+        let err = reader.records().next().unwrap().unwrap_err();
+        assert!(matches!(CsvError::from(err), CsvError::Parse(_)));
+    }
+}
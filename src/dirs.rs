@@ -0,0 +1,304 @@
+//! Files in the per-user cache, data and state directories of an
+//! application.
+//!
+//! The directories follow the conventions of the platform:
+//!
+//! | Base            | Linux and other unix                  | macOS                           | Windows          |
+//! |-----------------|---------------------------------------|---------------------------------|------------------|
+//! | [`Base::Cache`] | `$XDG_CACHE_HOME` or `~/.cache`       | `~/Library/Caches`              | `%LOCALAPPDATA%` |
+//! | [`Base::Data`]  | `$XDG_DATA_HOME` or `~/.local/share`  | `~/Library/Application Support` | `%APPDATA%`      |
+//! | [`Base::State`] | `$XDG_STATE_HOME` or `~/.local/state` | `~/Library/Application Support` | `%LOCALAPPDATA%` |
+//!
+//! Reading never creates any directory, and a base directory that can
+//! not be found, such as when `HOME` is not set, is just like a
+//! directory that does not contain the file.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::dirs::open_cache_optional;
+//! # use std::io;
+//!
+//! # fn main() -> io::Result<()> {
+//! if let Some(index) = open_cache_optional("app", "index.bin")? {
+//!     // Read the cached index ...
+//! }
+//! # Ok(())
+//! # }
+//! ````
+use crate::fs::{create_with_parents, timed};
+use crate::kinds::NOT_FOUND;
+use crate::{optional_at, IoOption};
+use std::env;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+
+/// Open a file in the cache directory of `app`, if it exists.
+///
+/// See [`AppDir::open`].
+#[track_caller]
+pub fn open_cache_optional<A, P>(app: A, name: P) -> IoOption<File>
+where
+    A: AsRef<Path>,
+    P: AsRef<Path>,
+{
+    let (dir, caller) = (AppDir::new(Base::Cache, app), Location::caller());
+    dir.open_as(
+        "open_cache_optional",
+        dir.base.path(),
+        name.as_ref(),
+        caller,
+    )
+}
+
+/// Open a file in the data directory of `app`, if it exists.
+///
+/// See [`AppDir::open`].
+#[track_caller]
+pub fn open_data_optional<A, P>(app: A, name: P) -> IoOption<File>
+where
+    A: AsRef<Path>,
+    P: AsRef<Path>,
+{
+    let (dir, caller) = (AppDir::new(Base::Data, app), Location::caller());
+    dir.open_as("open_data_optional", dir.base.path(), name.as_ref(), caller)
+}
+
+/// Open a file in the state directory of `app`, if it exists.
+///
+/// See [`AppDir::open`].
+#[track_caller]
+pub fn open_state_optional<A, P>(app: A, name: P) -> IoOption<File>
+where
+    A: AsRef<Path>,
+    P: AsRef<Path>,
+{
+    let (dir, caller) = (AppDir::new(Base::State, app), Location::caller());
+    dir.open_as(
+        "open_state_optional",
+        dir.base.path(),
+        name.as_ref(),
+        caller,
+    )
+}
+
+/// A per-user base directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// Files that can be removed, and recreated, at any time.
+    Cache,
+    /// Files that the application keeps, such as downloads.
+    Data,
+    /// State that should survive a restart, but is not important
+    /// enough to be data, such as history and logs.
+    State,
+}
+
+impl Base {
+    /// Get the path of this base directory.
+    ///
+    /// If the environment variable it depends on is not set, or is not
+    /// an absolute path, an error of kind `NotFound` is returned.
+    /// The directory itself may not exist.
+    pub fn path(self) -> io::Result<PathBuf> {
+        base_dir(self, |key| env::var_os(key))
+    }
+}
+
+// The base directories are found with `env`, rather than in the
+// environment directly, so they can be tested.
+
+#[cfg(windows)]
+fn base_dir(base: Base, env: impl Fn(&str) -> Option<OsString>) -> io::Result<PathBuf> {
+    let key = match base {
+        Base::Data => "APPDATA",
+        Base::Cache | Base::State => "LOCALAPPDATA",
+    };
+    env_dir(key, &env)
+}
+
+#[cfg(target_os = "macos")]
+fn base_dir(base: Base, env: impl Fn(&str) -> Option<OsString>) -> io::Result<PathBuf> {
+    Ok(env_dir("HOME", &env)?.join(match base {
+        Base::Cache => "Library/Caches",
+        Base::Data | Base::State => "Library/Application Support",
+    }))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn base_dir(base: Base, env: impl Fn(&str) -> Option<OsString>) -> io::Result<PathBuf> {
+    let (key, default) = match base {
+        Base::Cache => ("XDG_CACHE_HOME", ".cache"),
+        Base::Data => ("XDG_DATA_HOME", ".local/share"),
+        Base::State => ("XDG_STATE_HOME", ".local/state"),
+    };
+    // An unset or relative XDG variable means the default, as in the
+    // XDG Base Directory Specification.
+    env_dir(key, &env).or_else(|_| Ok(env_dir("HOME", &env)?.join(default)))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn base_dir(_: Base, _: impl Fn(&str) -> Option<OsString>) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "no per-user directories on this platform",
+    ))
+}
+
+fn env_dir(key: &str, env: impl Fn(&str) -> Option<OsString>) -> io::Result<PathBuf> {
+    env(key)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("environment variable {} is not set to a directory", key),
+            )
+        })
+}
+
+/// A directory of an application, in a [`Base`] directory.
+///
+/// # Examples
+/// ````no_run
+/// use io_result_optional::dirs::{AppDir, Base};
+/// use std::io::Write;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let state = AppDir::new(Base::State, "app").create_dirs(true);
+/// if state.open("last-run")?.is_none() {
+///     println!("Welcome!");
+/// }
+/// state.create("last-run")?.write_all(b"ok")?;
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Clone, Debug)]
+pub struct AppDir {
+    base: Base,
+    app: PathBuf,
+    create_dirs: bool,
+}
+
+impl AppDir {
+    /// The directory for `app` in `base`.
+    pub fn new<A: AsRef<Path>>(base: Base, app: A) -> Self {
+        AppDir {
+            base,
+            app: app.as_ref().into(),
+            create_dirs: false,
+        }
+    }
+
+    /// Set if [`create`](Self::create) should create the directory,
+    /// and any missing parents, if needed.
+    /// By default, it does not.
+    ///
+    /// Reading never creates any directory.
+    pub fn create_dirs(mut self, value: bool) -> Self {
+        self.create_dirs = value;
+        self
+    }
+
+    /// Get the path of `name` in this directory.
+    pub fn path<P: AsRef<Path>>(&self, name: P) -> io::Result<PathBuf> {
+        self.path_in(self.base.path(), name.as_ref())
+    }
+
+    // The private methods take the base directory, so they can be
+    // tested without changing the environment.
+
+    fn path_in(&self, base_dir: io::Result<PathBuf>, name: &Path) -> io::Result<PathBuf> {
+        Ok(base_dir?.join(&self.app).join(name))
+    }
+
+    /// Open the file `name` in this directory for reading, if it
+    /// exists.
+    ///
+    /// If the file, this directory, or the base directory does not
+    /// exist, the result is `Ok(None)`.
+    #[track_caller]
+    pub fn open<P: AsRef<Path>>(&self, name: P) -> IoOption<File> {
+        let caller = Location::caller();
+        self.open_as("AppDir::open", self.base.path(), name.as_ref(), caller)
+    }
+
+    fn open_as(
+        &self,
+        operation: &'static str,
+        base_dir: io::Result<PathBuf>,
+        name: &Path,
+        caller: &'static Location<'static>,
+    ) -> IoOption<File> {
+        match self.path_in(base_dir, name) {
+            Ok(path) => timed(operation, &path, || {
+                optional_at(File::open(&path), &NOT_FOUND, caller)
+            }),
+            Err(e) => optional_at(Err(e), &NOT_FOUND, caller),
+        }
+    }
+
+    /// Create the file `name` in this directory for writing, or
+    /// truncate it if it exists.
+    ///
+    /// If the directory does not exist, it is created if
+    /// [`create_dirs`](Self::create_dirs) is set, and otherwise an
+    /// error of kind `NotFound` is returned.
+    pub fn create<P: AsRef<Path>>(&self, name: P) -> io::Result<File> {
+        self.create_in(self.base.path(), name.as_ref())
+    }
+
+    fn create_in(&self, base_dir: io::Result<PathBuf>, name: &Path) -> io::Result<File> {
+        let path = self.path_in(base_dir, name)?;
+        if self.create_dirs {
+            create_with_parents(path)
+        } else {
+            File::create(path)
+        }
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::{base_dir, AppDir, Base};
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    #[test]
+    fn xdg_dirs() {
+        let env = |key: &str| match key {
+            "HOME" => Some("/home/user".into()),
+            "XDG_CACHE_HOME" => Some("/var/cache/user".into()),
+            "XDG_DATA_HOME" => Some("relative".into()),
+            _ => None,
+        };
+        let dir = |base| base_dir(base, env).unwrap();
+        assert_eq!(dir(Base::Cache), Path::new("/var/cache/user"));
+        assert_eq!(dir(Base::Data), Path::new("/home/user/.local/share"));
+        assert_eq!(dir(Base::State), Path::new("/home/user/.local/state"));
+        let no_home = base_dir(Base::State, |_: &str| None::<OsString>);
+        assert_eq!(no_home.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn open_and_create() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = || Ok(dir.path().to_owned());
+        let cache = AppDir::new(Base::Cache, "app");
+        let open = |cache: &AppDir| {
+            let caller = std::panic::Location::caller();
+            cache.open_as("AppDir::open", base(), "index".as_ref(), caller)
+        };
+        assert!(open(&cache).unwrap().is_none());
+        assert!(cache.create_in(base(), "index".as_ref()).is_err());
+        assert!(!dir.path().join("app").exists());
+        let cache = cache.create_dirs(true);
+        cache.create_in(base(), "index".as_ref()).unwrap();
+        assert!(open(&cache).unwrap().is_some());
+        let missing = Err(std::io::ErrorKind::NotFound.into());
+        assert!(cache.create_in(missing, "index".as_ref()).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Io errors that keep the path they are about.
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// An io error, with the path it is about.
+///
+/// The kind and path are kept as they are, rather than formatted into
+/// a message, and the original error is the source.
+/// This works as a `#[from]` target in `thiserror` enums.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::{IoResultOptional, OptionalIoError, WithPath};
+/// use std::fs;
+///
+/// #[derive(Debug, thiserror::Error)]
+/// enum ConfigError {
+///     #[error("failed to read configuration")]
+///     Read(#[from] OptionalIoError),
+///     #[error("bad configuration: {0}")]
+///     Parse(String),
+/// }
+///
+/// fn load(path: &str) -> Result<Option<String>, ConfigError> {
+///     Ok(fs::read_to_string(path).optional().with_path(path)?)
+/// }
+/// # assert!(matches!(load("nosuch.toml"), Ok(None)));
+/// ````
+#[derive(Debug)]
+pub struct OptionalIoError {
+    kind: ErrorKind,
+    path: Option<PathBuf>,
+    source: io::Error,
+}
+
+impl OptionalIoError {
+    /// Create an error about `path`.
+    pub fn new<P: Into<PathBuf>>(source: io::Error, path: P) -> Self {
+        OptionalIoError {
+            kind: source.kind(),
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    /// The kind of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The path that the error is about, if known.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The original io error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.source
+    }
+
+    /// Get the original io error.
+    pub fn into_io_error(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for OptionalIoError {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(out, "{}: {}", path.display(), self.source),
+            None => self.source.fmt(out),
+        }
+    }
+}
+
+impl Error for OptionalIoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// An error without a known path.
+impl From<io::Error> for OptionalIoError {
+    fn from(source: io::Error) -> Self {
+        OptionalIoError {
+            kind: source.kind(),
+            path: None,
+            source,
+        }
+    }
+}
+
+/// The error keeps its kind, and the path is added to the message.
+impl From<OptionalIoError> for io::Error {
+    fn from(error: OptionalIoError) -> Self {
+        match error.path {
+            Some(_) => io::Error::new(error.kind, error),
+            None => error.source,
+        }
+    }
+}
+
+/// Adding a path to the error of a result.
+pub trait WithPath<T> {
+    /// Make an error into an [`OptionalIoError`] about `path`.
+    fn with_path<P: Into<PathBuf>>(self, path: P) -> Result<T, OptionalIoError>;
+}
+
+impl<T> WithPath<T> for io::Result<T> {
+    fn with_path<P: Into<PathBuf>>(self, path: P) -> Result<T, OptionalIoError> {
+        self.map_err(|e| OptionalIoError::new(e, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OptionalIoError, WithPath};
+    use std::error::Error;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    #[test]
+    fn with_path() {
+        let error = fs::read("nosuch.file")
+            .with_path("nosuch.file")
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert_eq!(error.path(), Some(Path::new("nosuch.file")));
+        assert!(error.to_string().starts_with("nosuch.file: "));
+        assert!(error.source().is_some());
+
+        let error = io::Error::from(error);
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(error.to_string().starts_with("nosuch.file: "));
+    }
+
+    #[test]
+    fn without_path() {
+        let error = OptionalIoError::from(io::Error::from(io::ErrorKind::TimedOut));
+        assert_eq!(error.path(), None);
+        assert_eq!(error.to_string(), error.io_error().to_string());
+    }
+}
@@ -0,0 +1,208 @@
+//! Conventional exit codes for io errors.
+//!
+//! The codes are those of `sysexits.h`, which are used by many unix
+//! command line tools, such as `EX_NOINPUT` for a missing input file.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::exit::{exit_code, EX_NOINPUT};
+//! use std::fs::File;
+//!
+//! let error = File::open("nosuch.file").unwrap_err();
+//! assert_eq!(exit_code(&error), EX_NOINPUT);
+//! ````
+use crate::kinds::{is_fd_exhausted, TRANSIENT_KINDS};
+use crate::render::Report;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::process::{ExitCode, Termination};
+
+/// The command was used incorrectly.
+pub const EX_USAGE: u8 = 64;
+/// The input data was incorrect.
+pub const EX_DATAERR: u8 = 65;
+/// An input file did not exist or was not readable.
+pub const EX_NOINPUT: u8 = 66;
+/// A service is unavailable.
+pub const EX_UNAVAILABLE: u8 = 69;
+/// An error in the operating system, such as running out of memory.
+pub const EX_OSERR: u8 = 71;
+/// An output file could not be created.
+pub const EX_CANTCREAT: u8 = 73;
+/// An error occurred while doing io.
+pub const EX_IOERR: u8 = 74;
+/// A temporary failure; the user is invited to try again later.
+pub const EX_TEMPFAIL: u8 = 75;
+/// Permission was denied.
+pub const EX_NOPERM: u8 = 77;
+
+/// Get the conventional exit code for `error`.
+///
+/// Errors that may go away if retried, see [`TRANSIENT_KINDS`] and
+/// [`is_fd_exhausted`], give `EX_TEMPFAIL`, and errors without a more
+/// specific code give `EX_IOERR`.
+pub fn exit_code(error: &io::Error) -> u8 {
+    if is_fd_exhausted(error) {
+        return EX_TEMPFAIL;
+    }
+    match error.kind() {
+        ErrorKind::NotFound | ErrorKind::NotADirectory => EX_NOINPUT,
+        ErrorKind::PermissionDenied => EX_NOPERM,
+        ErrorKind::InvalidData | ErrorKind::UnexpectedEof => EX_DATAERR,
+        ErrorKind::InvalidInput | ErrorKind::InvalidFilename | ErrorKind::ArgumentListTooLong => {
+            EX_USAGE
+        }
+        ErrorKind::AlreadyExists | ErrorKind::IsADirectory | ErrorKind::ReadOnlyFilesystem => {
+            EX_CANTCREAT
+        }
+        ErrorKind::OutOfMemory => EX_OSERR,
+        kind if TRANSIENT_KINDS.contains(kind) => EX_TEMPFAIL,
+        ErrorKind::ConnectionRefused
+        | ErrorKind::HostUnreachable
+        | ErrorKind::NetworkUnreachable
+        | ErrorKind::NotConnected
+        | ErrorKind::Unsupported => EX_UNAVAILABLE,
+        _ => EX_IOERR,
+    }
+}
+
+/// An io error that the program should exit with.
+///
+/// This converts to an [`ExitCode`], given by [`exit_code`].
+///
+/// # Examples
+/// ````no_run
+/// use io_result_optional::exit::ExitError;
+/// use std::process::ExitCode;
+/// use std::fs;
+///
+/// fn run() -> Result<(), ExitError> {
+///     let input = fs::read_to_string("input.txt")?;
+///     // Handle the input ...
+///     Ok(())
+/// }
+///
+/// fn main() -> ExitCode {
+///     match run() {
+///         Ok(()) => ExitCode::SUCCESS,
+///         Err(e) => {
+///             eprintln!("Error: {}", e);
+///             e.into()
+///         }
+///     }
+/// }
+/// ````
+#[derive(Debug)]
+pub struct ExitError(pub io::Error);
+
+impl ExitError {
+    /// The conventional exit code for the error.
+    pub fn code(&self) -> u8 {
+        exit_code(&self.0)
+    }
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(out)
+    }
+}
+
+impl Error for ExitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl From<io::Error> for ExitError {
+    fn from(error: io::Error) -> Self {
+        ExitError(error)
+    }
+}
+
+impl From<ExitError> for ExitCode {
+    fn from(error: ExitError) -> Self {
+        ExitCode::from(error.code())
+    }
+}
+
+/// The result of a `main` function.
+///
+/// On error, this shows the error as a [`Report`] on stderr and exits
+/// with the code given by [`exit_code`], instead of the `Debug` output
+/// and exit code 1 that `main` gives when returning an `io::Result`.
+///
+/// # Examples
+/// ````no_run
+/// use io_result_optional::exit::Main;
+/// use std::fs;
+/// use std::io;
+///
+/// fn run() -> io::Result<()> {
+///     let input = fs::read_to_string("input.txt")?;
+///     // Handle the input ...
+///     Ok(())
+/// }
+///
+/// fn main() -> Main {
+///     run().into()
+/// }
+/// ````
+#[derive(Debug)]
+#[must_use]
+pub struct Main(pub Result<(), ExitError>);
+
+impl From<io::Result<()>> for Main {
+    fn from(result: io::Result<()>) -> Self {
+        Main(result.map_err(ExitError))
+    }
+}
+
+impl From<Result<(), ExitError>> for Main {
+    fn from(result: Result<(), ExitError>) -> Self {
+        Main(result)
+    }
+}
+
+impl From<io::Error> for Main {
+    fn from(error: io::Error) -> Self {
+        Main(Err(ExitError(error)))
+    }
+}
+
+impl Termination for Main {
+    fn report(self) -> ExitCode {
+        match self.0 {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprint!("{}", Report::new(&e.0));
+                e.into()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes() {
+        let code = |kind: ErrorKind| exit_code(&kind.into());
+        assert_eq!(code(ErrorKind::NotFound), EX_NOINPUT);
+        assert_eq!(code(ErrorKind::PermissionDenied), EX_NOPERM);
+        assert_eq!(code(ErrorKind::TimedOut), EX_TEMPFAIL);
+        assert_eq!(code(ErrorKind::ReadOnlyFilesystem), EX_CANTCREAT);
+        assert_eq!(code(ErrorKind::StorageFull), EX_IOERR);
+        let error = ExitError::from(io::Error::from(ErrorKind::InvalidData));
+        assert_eq!(error.code(), EX_DATAERR);
+    }
+
+    #[test]
+    fn main_report() {
+        assert_eq!(Main(Ok(())).report(), ExitCode::SUCCESS);
+        let main = Main::from(io::Error::from(ErrorKind::NotFound));
+        assert_eq!(main.report(), ExitCode::from(EX_NOINPUT));
+    }
+}
@@ -0,0 +1,126 @@
+//! Implementations of [`MaybeMissing`] for error types of other crates.
+//!
+//! Each implementation is enabled by the feature with the same name as
+//! the crate.
+use crate::MaybeMissing;
+
+/// An [`object_store::Error::NotFound`] is missing.
+#[cfg(feature = "object_store")]
+impl MaybeMissing for object_store::Error {
+    fn is_missing(&self) -> bool {
+        matches!(self, object_store::Error::NotFound { .. })
+    }
+}
+
+/// An [`opendal::Error`] of kind [`opendal::ErrorKind::NotFound`] is missing.
+#[cfg(feature = "opendal")]
+impl MaybeMissing for opendal::Error {
+    fn is_missing(&self) -> bool {
+        self.kind() == opendal::ErrorKind::NotFound
+    }
+}
+
+/// A [`reqwest::Error`] with status 404 Not Found is missing.
+///
+/// Note that reqwest only returns an error for a 404 response if
+/// asked to, e.g. by [`reqwest::Response::error_for_status`].
+/// Transport errors and other statuses are not missing.
+#[cfg(feature = "reqwest")]
+impl MaybeMissing for reqwest::Error {
+    fn is_missing(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::NOT_FOUND)
+    }
+}
+
+/// A [`ureq::Error::StatusCode`] 404 Not Found is missing.
+/// Transport errors and other statuses are not missing.
+#[cfg(feature = "ureq")]
+impl MaybeMissing for ureq::Error {
+    fn is_missing(&self) -> bool {
+        matches!(self, ureq::Error::StatusCode(404))
+    }
+}
+
+/// A [`rusqlite::Error::QueryReturnedNoRows`] is missing.
+#[cfg(feature = "rusqlite")]
+impl MaybeMissing for rusqlite::Error {
+    fn is_missing(&self) -> bool {
+        matches!(self, rusqlite::Error::QueryReturnedNoRows)
+    }
+}
+
+/// A [`sqlx::Error::RowNotFound`] is missing.
+#[cfg(feature = "sqlx")]
+impl MaybeMissing for sqlx::Error {
+    fn is_missing(&self) -> bool {
+        matches!(self, sqlx::Error::RowNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "object_store")]
+    #[test]
+    fn object_store_not_found() {
+        use crate::ResultOptional;
+        let result: Result<(), _> = Err(object_store::Error::NotFound {
+            path: "some/key".into(),
+            source: "no such key".into(),
+        });
+        assert!(result.optional().unwrap().is_none());
+        let result: Result<(), _> = Err(object_store::Error::Precondition {
+            path: "some/key".into(),
+            source: "changed".into(),
+        });
+        assert!(result.optional().is_err());
+    }
+
+    #[cfg(feature = "opendal")]
+    #[test]
+    fn opendal_not_found() {
+        use crate::ResultOptional;
+        use opendal::{Error, ErrorKind};
+        let result: Result<(), _> = Err(Error::new(ErrorKind::NotFound, "no such key"));
+        assert!(result.optional().unwrap().is_none());
+        let result: Result<(), _> = Err(Error::new(ErrorKind::PermissionDenied, "nope"));
+        assert!(result.optional().is_err());
+    }
+
+    #[cfg(feature = "rusqlite")]
+    #[test]
+    fn rusqlite_no_rows() {
+        use crate::ResultOptional;
+        let db = rusqlite::Connection::open_in_memory().unwrap();
+        let query = |n: i32| {
+            db.query_row("select 17 where ?1 > 0", [n], |row| row.get::<_, i32>(0))
+                .optional()
+                .unwrap()
+        };
+        assert_eq!(query(1), Some(17));
+        assert_eq!(query(0), None);
+        let result = db.query_row("no sql", [], |row| row.get::<_, i32>(0));
+        assert!(result.optional().is_err());
+    }
+
+    #[cfg(feature = "sqlx")]
+    #[test]
+    fn sqlx_row_not_found() {
+        use crate::ResultOptional;
+        let result: Result<(), _> = Err(sqlx::Error::RowNotFound);
+        assert!(result.optional().unwrap().is_none());
+        let result: Result<(), _> = Err(sqlx::Error::PoolTimedOut);
+        assert!(result.optional().is_err());
+    }
+
+    #[cfg(feature = "ureq")]
+    #[test]
+    fn ureq_not_found() {
+        use crate::ResultOptional;
+        let result: Result<(), _> = Err(ureq::Error::StatusCode(404));
+        assert!(result.optional().unwrap().is_none());
+        let result: Result<(), _> = Err(ureq::Error::StatusCode(500));
+        assert!(result.optional().is_err());
+        let result: Result<(), _> = Err(ureq::Error::ConnectionFailed);
+        assert!(result.optional().is_err());
+    }
+}
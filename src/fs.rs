@@ -0,0 +1,2476 @@
+//! Helpers for file system operations on paths that may not exist.
+//!
+//! Each helper treats a missing file as an ordinary outcome rather
+//! than an error, while any other error is returned as-is.
+//! System calls that are interrupted by a signal are retried, see
+//! [`set_retry_interrupted`].
+//! The quirks of network file systems, such as stale file handles, can
+//! be handled too, see [`set_nfs_quirks`].
+use crate::kinds::{is_nfs_quirk, is_stale, NOT_FOUND};
+use crate::outcome::{LoadOutcome, Outcome};
+use crate::retry::RetryPolicy;
+use crate::{optional_at, probe, KindSet, OptionalIoError};
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File, FileTimes, Permissions};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Deref;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Make an attempt on `path`, that found nothing if it gives `None`.
+///
+/// The attempt is timed with the `telemetry` feature, and errors
+/// tolerated in it are recorded with the path with the `audit` feature.
+/// The operation is the name of the public function making the
+/// attempt.
+pub(crate) fn timed<T>(
+    operation: &'static str,
+    path: &Path,
+    attempt: impl FnOnce() -> io::Result<Option<T>>,
+) -> io::Result<Option<T>> {
+    timed_found(operation, path, attempt, Option::is_some)
+}
+
+/// Make an attempt on `path`, where `found` tells if the value means
+/// that something was found, see [`timed`].
+pub(crate) fn timed_found<T>(
+    operation: &'static str,
+    path: &Path,
+    attempt: impl FnOnce() -> io::Result<T>,
+    found: impl FnOnce(&T) -> bool,
+) -> io::Result<T> {
+    #[cfg(feature = "audit")]
+    let attempt = || crate::audit::with_path(path, attempt);
+    #[cfg(feature = "telemetry")]
+    return crate::telemetry::timed(operation, path, attempt, found);
+    #[cfg(not(feature = "telemetry"))]
+    {
+        let _ = (operation, path, found);
+        attempt()
+    }
+}
+
+/// If system calls interrupted by a signal are retried.
+static RETRY_INTERRUPTED: AtomicBool = AtomicBool::new(true);
+
+/// Set if the helpers in this module retry system calls that are
+/// interrupted by a signal.
+///
+/// A system call, such as opening, reading or getting the metadata of
+/// a file, may fail with an `Interrupted` error when the process gets
+/// a signal, which is rare enough to be hard to reproduce.
+/// By default, such calls are retried.
+/// Turn that off to see the `Interrupted` errors, for example to stop
+/// what is going on when a signal arrives.
+pub fn set_retry_interrupted(value: bool) {
+    RETRY_INTERRUPTED.store(value, Ordering::Relaxed);
+}
+
+/// If the quirks of network file systems are handled.
+static NFS_QUIRKS: AtomicBool = AtomicBool::new(false);
+
+/// The number of times a system call giving an NFS quirk is retried.
+const NFS_RETRIES: u32 = 3;
+
+/// Set if the helpers in this module handle the quirks of network file
+/// systems, such as NFS.
+///
+/// When this is on, a system call that gives a
+/// [quirk](crate::kinds::is_nfs_quirk) is retried a few times, after a
+/// short delay.
+/// If the file handle is still [stale](crate::kinds::is_stale), the
+/// file has been removed or replaced, so the error is given the kind
+/// `NotFound`, and the helpers treat it as missing.
+/// Other quirks that remain are returned as-is.
+///
+/// This is off by default, since the retries make the helpers slower
+/// to fail.
+pub fn set_nfs_quirks(value: bool) {
+    NFS_QUIRKS.store(value, Ordering::Relaxed);
+}
+
+/// Make a system call, retrying it if it is interrupted, or gives an
+/// NFS quirk when those are handled.
+fn eintr<T>(call: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    eintr_with(
+        RETRY_INTERRUPTED.load(Ordering::Relaxed),
+        NFS_QUIRKS.load(Ordering::Relaxed),
+        call,
+    )
+}
+
+/// Make a system call, retrying it if it is interrupted and
+/// `retry_interrupted` is set, and handling NFS quirks if
+/// `nfs_quirks` is set.
+fn eintr_with<T>(
+    retry_interrupted: bool,
+    nfs_quirks: bool,
+    mut call: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut retries = 0;
+    loop {
+        match call() {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted && retry_interrupted => {}
+            Err(e) if nfs_quirks && is_nfs_quirk(&e) => {
+                if retries < NFS_RETRIES {
+                    retries += 1;
+                    thread::sleep(Duration::from_millis(10 << retries));
+                } else if is_stale(&e) {
+                    return Err(io::Error::new(io::ErrorKind::NotFound, e));
+                } else {
+                    return Err(e);
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Read the lines of a file that may not exist.
+///
+/// If the file does not exist, the returned iterator is empty.
+/// Any other error opening the file is returned, and errors while
+/// reading are yielded by the iterator.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::lines_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// for line in lines_optional(".appignore")? {
+///     let pattern = line?;
+///     // Handle the pattern ...
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn lines_optional<P: AsRef<Path>>(path: P) -> io::Result<Lines> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    Ok(Lines {
+        inner: timed("lines_optional", path, || {
+            optional_at(eintr(|| File::open(path)), &NOT_FOUND, caller)
+        })?
+        .map(|file| BufReader::new(file).lines()),
+    })
+}
+
+/// An iterator over the lines of a file that may not exist.
+///
+/// This is returned by [`lines_optional`].
+#[derive(Debug)]
+pub struct Lines {
+    inner: Option<io::Lines<BufReader<File>>>,
+}
+
+impl Iterator for Lines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.as_mut()?.next()
+    }
+}
+
+/// Set the permissions of a file, if it exists.
+///
+/// Return true if the permissions were set, and false if the file
+/// does not exist.
+/// Any other error, such as not being allowed to change the
+/// permissions, is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::set_permissions_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # let path = "nosuch.file";
+/// let mut perms = std::fs::metadata("Cargo.toml")?.permissions();
+/// perms.set_readonly(true);
+/// if !set_permissions_optional(path, perms)? {
+///     // The file was never created, so there was nothing to protect.
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn set_permissions_optional<P: AsRef<Path>>(path: P, perm: Permissions) -> io::Result<bool> {
+    set_permissions_as("set_permissions_optional", path.as_ref(), perm)
+}
+
+#[track_caller]
+fn set_permissions_as(operation: &'static str, path: &Path, perm: Permissions) -> io::Result<bool> {
+    let caller = Location::caller();
+    let result = timed(operation, path, || {
+        let result = eintr(|| fs::set_permissions(path, perm.clone()));
+        optional_at(result, &NOT_FOUND, caller)
+    });
+    Ok(result?.is_some())
+}
+
+/// Set the unix permission bits of a file, if it exists.
+///
+/// This is [`set_permissions_optional`] with permissions from `mode`,
+/// such as `0o600`.
+#[cfg(unix)]
+#[track_caller]
+pub fn chmod_if_exists<P: AsRef<Path>>(path: P, mode: u32) -> io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    set_permissions_as(
+        "chmod_if_exists",
+        path.as_ref(),
+        Permissions::from_mode(mode),
+    )
+}
+
+/// Truncate or extend a file to `len` bytes, if it exists.
+///
+/// Return true if the length was set, and false if the file does not
+/// exist, in which case it is not created.
+/// Any other error is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::set_len_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// # let path = dir.path().join("cache.bin");
+/// if !set_len_optional(&path, 1 << 20)? {
+///     // There is no cache to trim.
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn set_len_optional<P: AsRef<Path>>(path: P, len: u64) -> io::Result<bool> {
+    set_len_as("set_len_optional", path.as_ref(), len)
+}
+
+#[track_caller]
+fn set_len_as(operation: &'static str, path: &Path, len: u64) -> io::Result<bool> {
+    let caller = Location::caller();
+    let file = timed(operation, path, || {
+        let file = eintr(|| File::options().write(true).open(path));
+        optional_at(file, &NOT_FOUND, caller)
+    })?;
+    match file {
+        Some(file) => eintr(|| file.set_len(len)).map(|()| true),
+        None => Ok(false),
+    }
+}
+
+/// Truncate a file to be empty, if it exists.
+///
+/// This is [`set_len_optional`] with length zero.
+#[track_caller]
+pub fn truncate_if_exists<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    set_len_as("truncate_if_exists", path.as_ref(), 0)
+}
+
+/// Set the access and modification times of a file, if it exists.
+///
+/// Return true if the times were set, and false if the file does not
+/// exist, in which case it is not created.
+/// A `PermissionDenied` or `Unsupported` error, such as on a file
+/// system that does not keep times, is returned with the path of the
+/// file in the message, and any other error is returned as-is.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::set_times_optional;
+/// use std::fs::FileTimes;
+/// use std::time::{Duration, SystemTime};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// // Let the cache entry expire.
+/// let old = SystemTime::now() - Duration::from_secs(7 * 24 * 3600);
+/// set_times_optional("cache/index.bin", FileTimes::new().set_modified(old))?;
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn set_times_optional<P: AsRef<Path>>(path: P, times: FileTimes) -> io::Result<bool> {
+    set_times_as("set_times_optional", path.as_ref(), times)
+}
+
+#[track_caller]
+fn set_times_as(operation: &'static str, path: &Path, times: FileTimes) -> io::Result<bool> {
+    let caller = Location::caller();
+    let file = timed(operation, path, || {
+        optional_at(eintr(|| open_for_times(path)), &NOT_FOUND, caller)
+    });
+    let result = match file {
+        Ok(Some(file)) => eintr(|| file.set_times(times)).map(|()| true),
+        Ok(None) => Ok(false),
+        Err(e) => Err(e),
+    };
+    result.map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied | io::ErrorKind::Unsupported => {
+            io::Error::new(e.kind(), OptionalIoError::new(e, path))
+        }
+        _ => e,
+    })
+}
+
+/// Set the access and modification times of a file to now, if it
+/// exists.
+///
+/// This is like `touch`, but never creates the file; see
+/// [`set_times_optional`].
+#[track_caller]
+pub fn touch_if_exists<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    let now = SystemTime::now();
+    let times = FileTimes::new().set_accessed(now).set_modified(now);
+    set_times_as("touch_if_exists", path.as_ref(), times)
+}
+
+/// Open a file so that its times can be set, without needing
+/// permission to write its content.
+fn open_for_times(path: &Path) -> io::Result<File> {
+    let mut options = File::options();
+    options.read(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        // FILE_WRITE_ATTRIBUTES
+        options.access_mode(0x100);
+    }
+    options.open(path)
+}
+
+/// Copy the content of a file that may not exist to `writer`.
+///
+/// Return the number of bytes copied, or `Ok(None)` if the file does
+/// not exist.
+/// Reads and writes that are interrupted are retried, and any other
+/// error is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::copy_optional_to;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let mut backup = Vec::new();
+/// for name in ["config.toml", "state.json"] {
+///     if copy_optional_to(name, &mut backup)?.is_none() {
+///         eprintln!("Skipping {}, it does not exist", name);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn copy_optional_to<P, W>(src: P, writer: &mut W) -> io::Result<Option<u64>>
+where
+    P: AsRef<Path>,
+    W: Write + ?Sized,
+{
+    let (src, caller) = (src.as_ref(), Location::caller());
+    match timed("copy_optional_to", src, || {
+        optional_at(eintr(|| File::open(src)), &NOT_FOUND, caller)
+    })? {
+        // io::copy retries on Interrupted, for both reads and writes.
+        Some(mut file) => io::copy(&mut file, writer).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Write a file, unless the file system is read-only.
+///
+/// Return true if the file was written, and false if it could not be
+/// written because the file system is read-only, as is common in
+/// containers and on live systems.
+/// Any other error is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::write_best_effort;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// # let path = dir.path().join("last-seen");
+/// if !write_best_effort(&path, b"2024-01-01")? {
+///     // The state is not persisted, so it is recomputed next time.
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn write_best_effort<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<bool> {
+    let (path, contents) = (path.as_ref(), contents.as_ref());
+    let caller = Location::caller();
+    let written = timed("write_best_effort", path, || {
+        optional_at(eintr(|| fs::write(path, contents)), &READ_ONLY, caller)
+    });
+    Ok(written?.is_some())
+}
+
+/// Just [`io::ErrorKind::ReadOnlyFilesystem`], like in
+/// [`optional_read_only`](crate::IoResultOptional::optional_read_only).
+const READ_ONLY: KindSet = KindSet::new(&[io::ErrorKind::ReadOnlyFilesystem]);
+
+/// Read a text file that may not exist, replacing invalid UTF-8.
+///
+/// Any sequence of bytes that is not valid UTF-8 is replaced by the
+/// replacement character `U+FFFD`, so this never fails for the
+/// content of the file.
+/// See [`read_to_string_optional`] to choose what to do with invalid
+/// UTF-8 instead.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::read_to_string_lossy_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if let Some(log) = read_to_string_lossy_optional("other-tool.log")? {
+///     println!("{}", log);
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn read_to_string_lossy_optional<P: AsRef<Path>>(path: P) -> io::Result<Option<String>> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    Ok(timed("read_to_string_lossy_optional", path, || {
+        optional_at(eintr(|| fs::read(path)), &NOT_FOUND, caller)
+    })?
+    .map(|data| {
+        String::from_utf8(data)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+    }))
+}
+
+/// What [`read_to_string_optional`] does with a file that is not
+/// valid UTF-8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    /// Return an `InvalidData` error.
+    Fail,
+    /// Treat the file as unusable, like if it did not exist.
+    Skip,
+}
+
+/// Read a text file that may not exist.
+///
+/// A file that is not valid UTF-8 gives an `InvalidData` error, or is
+/// treated like a missing file, as given by `invalid`.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::{read_to_string_optional, InvalidUtf8};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let notes = read_to_string_optional("notes.txt", InvalidUtf8::Skip)?;
+/// # assert_eq!(notes, None);
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn read_to_string_optional<P: AsRef<Path>>(
+    path: P,
+    invalid: InvalidUtf8,
+) -> io::Result<Option<String>> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    let kinds = match invalid {
+        InvalidUtf8::Fail => NOT_FOUND,
+        InvalidUtf8::Skip => MISSING_OR_INVALID,
+    };
+    timed("read_to_string_optional", path, || {
+        optional_at(eintr(|| fs::read_to_string(path)), &kinds, caller)
+    })
+}
+
+/// Kinds of errors for a text file that is missing or not valid UTF-8.
+const MISSING_OR_INVALID: KindSet =
+    KindSet::new(&[io::ErrorKind::NotFound, io::ErrorKind::InvalidData]);
+
+/// Read a cache file that may not exist, or may be corrupt.
+///
+/// The content is passed to `validate`, that may check a checksum,
+/// magic number or version.
+/// If the file does not exist, or the content is not valid, the result
+/// is `Ok(None)`.
+/// Any other error reading the file is returned.
+/// See [`ReadCachedOptions`] to remove an invalid cache file.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::read_cached_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let index = read_cached_optional("cache/index.bin", |data| data.starts_with(b"IDX2"))?;
+/// # assert!(index.is_none());
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn read_cached_optional<P, F>(path: P, validate: F) -> io::Result<Option<Vec<u8>>>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&[u8]) -> bool,
+{
+    ReadCachedOptions::new().read(path, validate)
+}
+
+/// Options for [`read_cached_optional`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReadCachedOptions {
+    remove_invalid: bool,
+}
+
+impl ReadCachedOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set if a cache file with invalid content should be removed.
+    /// By default, it is left as it is.
+    pub fn remove_invalid(mut self, value: bool) -> Self {
+        self.remove_invalid = value;
+        self
+    }
+
+    /// Read the cache file `path`, if it exists and `validate` accepts
+    /// its content.
+    #[track_caller]
+    pub fn read<P, F>(&self, path: P, validate: F) -> io::Result<Option<Vec<u8>>>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&[u8]) -> bool,
+    {
+        let (path, caller) = (path.as_ref(), Location::caller());
+        match timed("read_cached_optional", path, || {
+            optional_at(eintr(|| fs::read(path)), &NOT_FOUND, caller)
+        })? {
+            Some(data) if validate(&data) => Ok(Some(data)),
+            Some(_) => {
+                if self.remove_invalid {
+                    // The cache is ignored even if it can not be removed.
+                    let _ = fs::remove_file(path);
+                }
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Sync a file to storage, if that is possible.
+///
+/// Return true if the file was synced, and false if syncing is not
+/// supported for it, such as for a pipe or on some network file
+/// systems, which give an `Unsupported` or `InvalidInput` error.
+/// Any other error, such as a failure to write back data, is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::sync_best_effort;
+/// use std::fs::File;
+/// use std::io::Write;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// # let path = dir.path().join("journal");
+/// let mut file = File::create(&path)?;
+/// file.write_all(b"entry")?;
+/// sync_best_effort(&file)?;
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn sync_best_effort(file: &File) -> io::Result<bool> {
+    let caller = Location::caller();
+    // There is no path for an open file.
+    let synced = timed("sync_best_effort", Path::new(""), || {
+        optional_at(eintr(|| file.sync_all()), &SYNC_UNSUPPORTED, caller)
+    });
+    Ok(synced?.is_some())
+}
+
+/// Kinds of errors from syncing a file that does not support it.
+const SYNC_UNSUPPORTED: KindSet =
+    KindSet::new(&[io::ErrorKind::Unsupported, io::ErrorKind::InvalidInput]);
+
+/// Open an existing file for reading and writing.
+///
+/// Return `Ok(None)` if the file does not exist, in which case it is
+/// not created.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::open_rw_optional;
+/// use std::io::{Read, Seek, SeekFrom, Write};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if let Some(mut file) = open_rw_optional("counter")? {
+///     let mut count = String::new();
+///     file.read_to_string(&mut count)?;
+///     let count: u64 = count.trim().parse().unwrap_or(0);
+///     file.seek(SeekFrom::Start(0))?;
+///     write!(file, "{}", count + 1)?;
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn open_rw_optional<P: AsRef<Path>>(path: P) -> io::Result<Option<File>> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    timed("open_rw_optional", path, || {
+        let file = eintr(|| File::options().read(true).write(true).open(path));
+        optional_at(file, &NOT_FOUND, caller)
+    })
+}
+
+/// Open a file, refusing to follow it if it is a symbolic link.
+///
+/// The file is opened with `options`, with the `O_NOFOLLOW` flag
+/// added (replacing any other custom flags).
+/// Only the last component of `path` is checked, so the directories
+/// leading to it should be trusted.
+///
+/// Return the opened file, or whether it is missing or a symbolic link.
+/// Any other error is returned.
+///
+/// This is for privileged programs working in directories that other
+/// users can write to, where following a symbolic link might let them
+/// read or write files they should not.
+///
+/// # Examples
+/// ````no_run
+/// use io_result_optional::fs::{open_nofollow, NoFollow};
+/// use std::fs::File;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let mut options = File::options();
+/// options.write(true).truncate(true);
+/// match open_nofollow("/home/user/.app/state", &options)? {
+///     NoFollow::File(file) => { /* Write the state ... */ }
+///     NoFollow::Missing => (),
+///     NoFollow::Symlink => eprintln!("Refused: the state file is a symlink"),
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[cfg(unix)]
+#[track_caller]
+pub fn open_nofollow<P: AsRef<Path>>(path: P, options: &fs::OpenOptions) -> io::Result<NoFollow> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let (path, caller) = (path.as_ref(), Location::caller());
+    let opened = || {
+        let result = eintr(|| options.clone().custom_flags(libc::O_NOFOLLOW).open(path));
+        match optional_at(result, &NOT_FOUND, caller) {
+            Ok(Some(file)) => Ok(NoFollow::File(file)),
+            Ok(None) => Ok(NoFollow::Missing),
+            Err(e) if is_symlink_refusal(&e) => Ok(NoFollow::Symlink),
+            Err(e) => Err(e),
+        }
+    };
+    timed_found("open_nofollow", path, opened, |opened| {
+        !matches!(opened, NoFollow::Missing)
+    })
+}
+
+/// Check if `error` is what opening a symbolic link with
+/// `O_NOFOLLOW` gives.
+#[cfg(unix)]
+fn is_symlink_refusal(error: &io::Error) -> bool {
+    let code = error.raw_os_error();
+    code == Some(libc::ELOOP)
+        || (cfg!(any(target_os = "freebsd", target_os = "dragonfly")) && code == Some(libc::EMLINK))
+}
+
+/// The result of [`open_nofollow`].
+#[cfg(unix)]
+#[derive(Debug)]
+pub enum NoFollow {
+    /// The file was opened.
+    File(File),
+    /// The file does not exist.
+    Missing,
+    /// The path is a symbolic link, so it was not opened.
+    Symlink,
+}
+
+/// Check if a file that may not exist can be opened for reading.
+///
+/// This actually opens the file, rather than looking at its mode
+/// bits, so access control lists, mount options and the like are
+/// respected.
+/// Return `Ok(None)` if the file does not exist, and `Ok(Some(false))`
+/// if permission to read it is denied.
+/// Any other error is returned.
+///
+/// Note that the answer may have changed by the time the file is
+/// opened for real.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::can_read;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if can_read("/etc/shadow")? == Some(false) {
+///     eprintln!("Warning: Running without access to passwords.");
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn can_read<P: AsRef<Path>>(path: P) -> io::Result<Option<bool>> {
+    can_open("can_read", path.as_ref(), File::options().read(true), &[])
+}
+
+/// Check if a file that may not exist can be opened for writing.
+///
+/// The file is opened, but not truncated or otherwise changed.
+/// Return `Ok(None)` if the file does not exist, and `Ok(Some(false))`
+/// if permission to write it is denied or the file system is read-only.
+/// Any other error is returned.
+///
+/// See [`can_read`].
+#[track_caller]
+pub fn can_write<P: AsRef<Path>>(path: P) -> io::Result<Option<bool>> {
+    let read_only = [io::ErrorKind::ReadOnlyFilesystem];
+    can_open(
+        "can_write",
+        path.as_ref(),
+        File::options().write(true),
+        &read_only,
+    )
+}
+
+#[track_caller]
+fn can_open(
+    operation: &'static str,
+    path: &Path,
+    options: &fs::OpenOptions,
+    denied: &[io::ErrorKind],
+) -> io::Result<Option<bool>> {
+    let caller = Location::caller();
+    timed(operation, path, || {
+        match optional_at(eintr(|| options.open(path)), &NOT_FOUND, caller) {
+            Ok(found) => Ok(found.map(|_| true)),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied || denied.contains(&e.kind()) => {
+                Ok(Some(false))
+            }
+            Err(e) => Err(e),
+        }
+    })
+}
+
+/// Create a file, and any missing parent directories.
+///
+/// The file is created, or truncated if it exists, and opened for
+/// writing, like with [`File::create`].
+/// If the parent directory does not exist, it is created first,
+/// along with any missing ancestors.
+/// Directories created by another process at the same time are fine.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::create_with_parents;
+/// use std::io::Write;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// # let path = dir.path().join("state/app/last-run");
+/// let mut state = create_with_parents(&path)?;
+/// state.write_all(b"ok")?;
+/// # Ok(())
+/// # }
+/// ````
+pub fn create_with_parents<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let path = path.as_ref();
+    let create = || match eintr(|| File::create(path)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            // create_dir_all accepts directories that already exist,
+            // even if they were created by someone else meanwhile.
+            create_parent(path)?;
+            eintr(|| File::create(path))
+        }
+        result => result,
+    };
+    timed_found("create_with_parents", path, create, |_| true)
+}
+
+/// Create a symbolic link at `link` pointing to `target`, unless it
+/// already exists.
+///
+/// If `link` already is a symbolic link to `target`, nothing is done.
+/// If it is a symbolic link to something else, it is replaced if
+/// `replace` is true, and otherwise an error of kind `AlreadyExists`
+/// is returned.
+/// Anything at `link` that is not a symbolic link is never replaced,
+/// but gives an `AlreadyExists` error.
+///
+/// On unix, replacing is atomic: `link` is always either the old or
+/// the new symbolic link.
+///
+/// # Examples
+/// ````no_run
+/// use io_result_optional::fs::{symlink_tolerant, SymlinkOutcome};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// match symlink_tolerant("releases/1.2.3", "current", true)? {
+///     SymlinkOutcome::Created => println!("Installed"),
+///     SymlinkOutcome::AlreadyPresent => println!("Already up to date"),
+///     SymlinkOutcome::Replaced(old) => println!("Upgraded from {}", old.display()),
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[cfg(any(unix, windows))]
+pub fn symlink_tolerant<P: AsRef<Path>, Q: AsRef<Path>>(
+    target: P,
+    link: Q,
+    replace: bool,
+) -> io::Result<SymlinkOutcome> {
+    let (target, link) = (target.as_ref(), link.as_ref());
+    let create = || symlink_or_existing(target, link, replace);
+    timed_found("symlink_tolerant", link, create, |_| true)
+}
+
+#[cfg(any(unix, windows))]
+fn symlink_or_existing(target: &Path, link: &Path, replace: bool) -> io::Result<SymlinkOutcome> {
+    match eintr(|| symlink(target, link)) {
+        Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => (),
+        result => return result.map(|()| SymlinkOutcome::Created),
+    }
+    let existing = match eintr(|| fs::read_link(link)) {
+        Ok(existing) => existing,
+        Err(ref e) if e.kind() == io::ErrorKind::InvalidInput => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a symbolic link", link.display()),
+            ));
+        }
+        Err(e) => return Err(e),
+    };
+    if existing == target {
+        Ok(SymlinkOutcome::AlreadyPresent)
+    } else if replace {
+        replace_symlink(target, link)?;
+        Ok(SymlinkOutcome::Replaced(existing))
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{} links to {}, not {}",
+                link.display(),
+                existing.display(),
+                target.display(),
+            ),
+        ))
+    }
+}
+
+/// What [`symlink_tolerant`] did.
+#[cfg(any(unix, windows))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SymlinkOutcome {
+    /// There was nothing at the link path, so the link was created.
+    Created,
+    /// The link already pointed to the target, so nothing was done.
+    AlreadyPresent,
+    /// The link pointed to the contained path, and was replaced.
+    Replaced(PathBuf),
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    use std::os::windows::fs::{symlink_dir, symlink_file};
+    let resolved = link.parent().unwrap_or_else(|| Path::new("")).join(target);
+    if resolved.is_dir() {
+        symlink_dir(target, link)
+    } else {
+        symlink_file(target, link)
+    }
+}
+
+/// Create a new link next to `link` and rename it into place.
+#[cfg(unix)]
+fn replace_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    let tmp = temp_sibling(link);
+    symlink(target, &tmp)?;
+    fs::rename(&tmp, link).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Windows can't rename over a directory link, so remove it first.
+#[cfg(windows)]
+fn replace_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    fs::remove_file(link).or_else(|_| fs::remove_dir(link))?;
+    symlink(target, link)
+}
+
+/// Create a hard link `dst` to the file `src`, tolerating that `src`
+/// does not exist or that `dst` is already the same file.
+///
+/// This is [`HardLinkOptions::link`] with both
+/// [`missing_source`](HardLinkOptions::missing_source) and
+/// [`existing_identical`](HardLinkOptions::existing_identical) enabled.
+///
+/// # Examples
+/// ````no_run
+/// use io_result_optional::fs::hard_link_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if hard_link_optional("incoming/blob", "store/ab/cdef")?.is_none() {
+///     // The blob was removed before it could be stored.
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn hard_link_optional<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+) -> io::Result<Option<HardLinkOutcome>> {
+    HardLinkOptions::new()
+        .missing_source(true)
+        .existing_identical(true)
+        .link(src, dst)
+}
+
+/// Options for what to tolerate when creating a hard link.
+///
+/// With all options disabled, which is the default, this works just
+/// like [`std::fs::hard_link`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HardLinkOptions {
+    missing_source: bool,
+    existing_identical: bool,
+}
+
+impl HardLinkOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set if a missing source should give `Ok(None)` rather than an
+    /// error.
+    pub fn missing_source(mut self, value: bool) -> Self {
+        self.missing_source = value;
+        self
+    }
+
+    /// Set if an existing destination that is identical to the source
+    /// should be accepted.
+    /// The destination is identical if it is the same file as the
+    /// source, or has the same content.
+    pub fn existing_identical(mut self, value: bool) -> Self {
+        self.existing_identical = value;
+        self
+    }
+
+    /// Create a hard link `dst` to the file `src`.
+    #[track_caller]
+    pub fn link<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        src: P,
+        dst: Q,
+    ) -> io::Result<Option<HardLinkOutcome>> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let caller = Location::caller();
+        timed("hard_link_optional", src, || {
+            match eintr(|| fs::hard_link(src, dst)) {
+                Ok(()) => Ok(Some(HardLinkOutcome::Linked)),
+                // The error may be about the parent of dst, so check src.
+                Err(e) if self.missing_source && e.kind() == io::ErrorKind::NotFound => {
+                    match probe(eintr(|| fs::symlink_metadata(src)), &NOT_FOUND)? {
+                        Some(_) => Err(e),
+                        None => optional_at(Err(e), &NOT_FOUND, caller),
+                    }
+                }
+                Err(e) if self.existing_identical && e.kind() == io::ErrorKind::AlreadyExists => {
+                    if identical(src, dst)? {
+                        Ok(Some(HardLinkOutcome::AlreadyLinked))
+                    } else {
+                        Err(e)
+                    }
+                }
+                Err(e) => Err(e),
+            }
+        })
+    }
+}
+
+/// What [`HardLinkOptions::link`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HardLinkOutcome {
+    /// The link was created.
+    Linked,
+    /// The destination already existed and was identical to the source.
+    AlreadyLinked,
+}
+
+fn identical(a: &Path, b: &Path) -> io::Result<bool> {
+    let (meta_a, meta_b) = (eintr(|| fs::metadata(a))?, eintr(|| fs::metadata(b))?);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if (meta_a.dev(), meta_a.ino()) == (meta_b.dev(), meta_b.ino()) {
+            return Ok(true);
+        }
+    }
+    if !meta_a.is_file() || !meta_b.is_file() || meta_a.len() != meta_b.len() {
+        return Ok(false);
+    }
+    let (mut a, mut b) = (eintr(|| File::open(a))?, eintr(|| File::open(b))?);
+    let (mut buf_a, mut buf_b) = ([0; 8192], [0; 8192]);
+    loop {
+        let len = eintr(|| a.read(&mut buf_a))?;
+        if len == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..len])?;
+        if buf_a[..len] != buf_b[..len] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Move a file that may not exist, even to another file system.
+///
+/// The file is renamed if possible.
+/// If `src` and `dst` are on different file systems, the file is
+/// instead copied to a temporary file next to `dst`, which is synced
+/// and renamed to `dst`, and then `src` is removed.
+/// If the copy fails, the temporary file is removed and `src` is left
+/// as it was.
+///
+/// Return what was done, or `Ok(None)` if `src` does not exist.
+///
+/// # Examples
+/// ````no_run
+/// use io_result_optional::fs::move_file;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if move_file("/tmp/upload.part", "/srv/data/upload")?.is_none() {
+///     // The upload was cancelled.
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+) -> io::Result<Option<MoveOutcome>> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    let caller = Location::caller();
+    timed("move_file", src, || match eintr(|| fs::rename(src, dst)) {
+        Ok(()) => Ok(Some(MoveOutcome::Renamed)),
+        // The error may be about the parent of dst, so check src.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            match probe(eintr(|| fs::symlink_metadata(src)), &NOT_FOUND)? {
+                Some(_) => Err(e),
+                None => optional_at(Err(e), &NOT_FOUND, caller),
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_replace(src, dst)?;
+            fs::remove_file(src)?;
+            Ok(Some(MoveOutcome::Copied))
+        }
+        Err(e) => Err(e),
+    })
+}
+
+/// What [`move_file`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveOutcome {
+    /// The file was renamed.
+    Renamed,
+    /// The file was copied to another file system, and then removed.
+    Copied,
+}
+
+/// Copy `src` to `dst`, through a temporary file, so that `dst` is
+/// either complete or untouched.
+fn copy_replace(src: &Path, dst: &Path) -> io::Result<()> {
+    let tmp = temp_sibling(dst);
+    let copy = || -> io::Result<()> {
+        eintr(|| fs::copy(src, &tmp))?;
+        eintr(|| File::open(&tmp))?.sync_all()?;
+        eintr(|| fs::rename(&tmp, dst))
+    };
+    copy().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Compute the total size of the files in a directory tree, if it
+/// exists.
+///
+/// If `path` does not exist, `Ok(None)` is returned.
+/// If it is a file, its size is returned.
+///
+/// Files and directories that disappear while the tree is traversed
+/// count as zero bytes.
+/// Other errors for things in the tree, such as directories that can't
+/// be read, are collected in [`DirSize::errors`] rather than stopping
+/// the traversal.
+/// Symbolic links are not followed.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::dir_size_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if let Some(size) = dir_size_optional("target/cache")? {
+///     println!("The cache uses {} bytes", size.bytes);
+///     for (path, err) in &size.errors {
+///         eprintln!("Failed to check {}: {}", path.display(), err);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn dir_size_optional<P: AsRef<Path>>(path: P) -> io::Result<Option<DirSize>> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    timed("dir_size_optional", path, || dir_size(path, caller))
+}
+
+fn dir_size(path: &Path, caller: &'static Location<'static>) -> io::Result<Option<DirSize>> {
+    let meta = match optional_at(eintr(|| fs::symlink_metadata(path)), &NOT_FOUND, caller)? {
+        Some(meta) => meta,
+        None => return Ok(None),
+    };
+    if !meta.is_dir() {
+        return Ok(Some(DirSize {
+            bytes: meta.len(),
+            errors: Vec::new(),
+        }));
+    }
+    let mut size = DirSize {
+        bytes: 0,
+        errors: Vec::new(),
+    };
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut visit = || -> io::Result<()> {
+            for entry in eintr(|| fs::read_dir(&dir))? {
+                let entry = entry?;
+                match probe(eintr(|| entry.metadata()), &NOT_FOUND) {
+                    Ok(Some(meta)) if meta.is_dir() => dirs.push(entry.path()),
+                    Ok(Some(meta)) => size.bytes += meta.len(),
+                    Ok(None) => (),
+                    Err(e) => size.errors.push((entry.path(), e)),
+                }
+            }
+            Ok(())
+        };
+        if let Err(e) = probe(visit(), &NOT_FOUND) {
+            size.errors.push((dir, e));
+        }
+    }
+    Ok(Some(size))
+}
+
+/// The result of [`dir_size_optional`].
+#[derive(Debug)]
+pub struct DirSize {
+    /// The total size in bytes of the files found.
+    pub bytes: u64,
+    /// Errors for paths that could not be checked.
+    pub errors: Vec<(PathBuf, io::Error)>,
+}
+
+/// Remove the empty directories in a directory tree, as far as
+/// possible.
+///
+/// The tree is walked bottom-up, so a directory that only contains
+/// empty directories is removed too.
+/// `root` itself is kept, and symbolic links are not followed.
+/// Return the number of directories removed.
+///
+/// A directory that is not empty is just left, and anything that is
+/// removed by someone else meanwhile is fine, as is a `root` that does
+/// not exist, also in [strict mode](crate::strict).
+/// Any other error is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::prune_empty_dirs;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let removed = prune_empty_dirs("target/cache")?;
+/// println!("Removed {} empty directories", removed);
+/// # Ok(())
+/// # }
+/// ````
+pub fn prune_empty_dirs<P: AsRef<Path>>(root: P) -> io::Result<usize> {
+    let root = root.as_ref();
+    let removed = timed("prune_empty_dirs", root, || prune(root))?;
+    Ok(removed.unwrap_or(0))
+}
+
+/// Remove the empty directories in `root`, which is `None` if it does
+/// not exist.
+fn prune(root: &Path) -> io::Result<Option<usize>> {
+    let entries = match probe(eintr(|| fs::read_dir(root)), &NOT_FOUND)? {
+        Some(entries) => entries,
+        None => return Ok(None),
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let entry = entry?;
+        if probe(entry.file_type(), &NOT_FOUND)?.is_some_and(|t| t.is_dir()) {
+            let dir = entry.path();
+            removed += prune(&dir)?.unwrap_or(0);
+            if remove_empty_dir(&dir)? == DirRemoval::Removed {
+                removed += 1;
+            }
+        }
+    }
+    Ok(Some(removed))
+}
+
+/// Remove a directory if it is empty and exists.
+///
+/// A directory that is not empty, or does not exist, is not an error,
+/// but is told apart by the returned [`DirRemoval`].
+/// Any other error, such as `path` not being a directory, is returned.
+///
+/// In [strict mode](crate::strict), a directory that does not exist
+/// gives a `NotFound` error rather than [`DirRemoval::Missing`], like
+/// any other optional access.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::{remove_dir_if_empty, DirRemoval};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if remove_dir_if_empty("downloads/partial")? == DirRemoval::NotEmpty {
+///     println!("Keeping the partial downloads");
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn remove_dir_if_empty<P: AsRef<Path>>(path: P) -> io::Result<DirRemoval> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    let removed = timed("remove_dir_if_empty", path, || {
+        match eintr(|| fs::remove_dir(path)) {
+            Err(e) if e.kind() == io::ErrorKind::DirectoryNotEmpty => Ok(Some(false)),
+            result => optional_at(result.map(|()| true), &NOT_FOUND, caller),
+        }
+    })?;
+    Ok(match removed {
+        Some(true) => DirRemoval::Removed,
+        Some(false) => DirRemoval::NotEmpty,
+        None => DirRemoval::Missing,
+    })
+}
+
+/// Remove a directory if it is empty, where a directory removed by
+/// someone else meanwhile is expected, also in strict mode.
+fn remove_empty_dir(path: &Path) -> io::Result<DirRemoval> {
+    match probe(eintr(|| fs::remove_dir(path)), &NOT_FOUND) {
+        Ok(Some(())) => Ok(DirRemoval::Removed),
+        Ok(None) => Ok(DirRemoval::Missing),
+        Err(e) if e.kind() == io::ErrorKind::DirectoryNotEmpty => Ok(DirRemoval::NotEmpty),
+        Err(e) => Err(e),
+    }
+}
+
+/// What [`remove_dir_if_empty`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirRemoval {
+    /// The directory was removed.
+    Removed,
+    /// The directory was not empty, so it was kept.
+    NotEmpty,
+    /// The directory did not exist.
+    Missing,
+}
+
+/// Get the canonical, absolute form of a path that may not exist.
+///
+/// If the path does not exist, `Ok(None)` is returned.
+/// See [`std::fs::canonicalize`].
+#[track_caller]
+pub fn canonicalize_optional<P: AsRef<Path>>(path: P) -> io::Result<Option<PathBuf>> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    timed("canonicalize_optional", path, || {
+        optional_at(eintr(|| fs::canonicalize(path)), &NOT_FOUND, caller)
+    })
+}
+
+/// Get the canonical, absolute form of the longest existing prefix of
+/// a path, with the rest of the path appended.
+///
+/// This is what you usually want for a path that is about to be
+/// created.
+/// The non-existing part is appended as-is, since there is no way to
+/// tell what it would resolve to.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::canonicalize_lenient;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let out = canonicalize_lenient("target/../target/new/report.html")?;
+/// assert!(out.ends_with("target/new/report.html"));
+/// assert!(out.is_absolute());
+/// # Ok(())
+/// # }
+/// ````
+pub fn canonicalize_lenient<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    timed_found("canonicalize_lenient", path, || lenient(path), |_| true)
+}
+
+fn lenient(path: &Path) -> io::Result<PathBuf> {
+    for prefix in path.ancestors() {
+        let existing = if prefix.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            prefix
+        };
+        if let Some(canonical) = probe(eintr(|| fs::canonicalize(existing)), &NOT_FOUND)? {
+            return match path.strip_prefix(prefix) {
+                Ok(rest) if !rest.as_os_str().is_empty() => Ok(canonical.join(rest)),
+                _ => Ok(canonical),
+            };
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no part of {} exists", path.display()),
+    ))
+}
+
+/// Read a file if it exists, and otherwise use built-in default
+/// content.
+///
+/// Return the content, with where it came from.
+/// Any error other than the file not existing is returned, rather
+/// than silently falling back to the default.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::{load_or_embedded, Source};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # const DEFAULT_CONFIG: &[u8] = b"answer = 42";
+/// // const DEFAULT_CONFIG: &[u8] = include_bytes!("default.toml");
+/// let config = load_or_embedded("app.toml", DEFAULT_CONFIG)?;
+/// if let Source::File(path) = &config.source {
+///     println!("Using configuration from {}", path.display());
+/// }
+/// # assert_eq!(&config[..], DEFAULT_CONFIG);
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn load_or_embedded<P: AsRef<Path>>(
+    path: P,
+    default: &'static [u8],
+) -> io::Result<Defaulted<Cow<'static, [u8]>>> {
+    let (path, caller) = (path.as_ref(), Location::caller());
+    Ok(
+        match timed("load_or_embedded", path, || {
+            optional_at(eintr(|| fs::read(path)), &NOT_FOUND, caller)
+        })? {
+            Some(content) => Defaulted::new(Cow::Owned(content), Source::File(path.into())),
+            None => Defaulted::new(Cow::Borrowed(default), Source::BuiltinDefault),
+        },
+    )
+}
+
+/// Open a file if it exists, and otherwise read the given bytes.
+///
+/// This is like [`load_or_embedded`], but the file is not read up
+/// front, so the reader can be used the same way for large assets
+/// whether they are on disk or built in.
+/// Any error other than the file not existing is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::open_or_memory;
+/// use std::io::{self, Read, Seek, SeekFrom};
+///
+/// # fn main() -> io::Result<()> {
+/// # const FONT: &[u8] = b"font data";
+/// // const FONT: &[u8] = include_bytes!("default.ttf");
+/// let mut font = open_or_memory("assets/font.ttf", FONT)?;
+/// font.seek(SeekFrom::Start(5))?;
+/// let mut data = String::new();
+/// font.read_to_string(&mut data)?;
+/// # assert_eq!(data, "data");
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn open_or_memory<P, B>(path: P, bytes: B) -> io::Result<FileOrMemory<B>>
+where
+    P: AsRef<Path>,
+    B: AsRef<[u8]>,
+{
+    let (path, caller) = (path.as_ref(), Location::caller());
+    Ok(
+        match timed("open_or_memory", path, || {
+            optional_at(eintr(|| File::open(path)), &NOT_FOUND, caller)
+        })? {
+            Some(file) => FileOrMemory::File(file),
+            None => FileOrMemory::Memory(Cursor::new(bytes)),
+        },
+    )
+}
+
+/// A reader that is either a file or bytes in memory.
+///
+/// This is returned by [`open_or_memory`].
+#[derive(Debug)]
+pub enum FileOrMemory<B> {
+    /// The file was opened.
+    File(File),
+    /// The file did not exist, so the given bytes are read.
+    Memory(Cursor<B>),
+}
+
+impl<B> FileOrMemory<B> {
+    /// Return true if this reads the given bytes rather than a file.
+    pub fn is_memory(&self) -> bool {
+        matches!(self, FileOrMemory::Memory(_))
+    }
+}
+
+impl<B: AsRef<[u8]>> Read for FileOrMemory<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            FileOrMemory::File(file) => file.read(buf),
+            FileOrMemory::Memory(bytes) => bytes.read(buf),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]>> Seek for FileOrMemory<B> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            FileOrMemory::File(file) => file.seek(pos),
+            FileOrMemory::Memory(bytes) => bytes.seek(pos),
+        }
+    }
+}
+
+/// Load a file if it exists, and otherwise use the default value.
+///
+/// The file is loaded by `load`, and the value is returned with where
+/// it came from.
+/// Any error other than the file not existing is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::load_or_default;
+/// use std::fs;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let motd = load_or_default("motd.txt", |path| fs::read_to_string(path))?;
+/// println!("Message of the day (from {}): {}", motd.source, *motd);
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn load_or_default<P, T, F>(path: P, load: F) -> io::Result<Defaulted<T>>
+where
+    P: AsRef<Path>,
+    T: Default,
+    F: FnOnce(&Path) -> io::Result<T>,
+{
+    let (path, caller) = (path.as_ref(), Location::caller());
+    Ok(
+        match timed("load_or_default", path, || {
+            optional_at(load(path), &NOT_FOUND, caller)
+        })? {
+            Some(value) => Defaulted::new(value, Source::File(path.into())),
+            None => Defaulted::new(T::default(), Source::BuiltinDefault),
+        },
+    )
+}
+
+/// A value, with where it came from.
+///
+/// This is returned by [`load_or_embedded`] and [`load_or_default`],
+/// and dereferences to the value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Defaulted<T> {
+    /// The value.
+    pub value: T,
+    /// Where the value came from.
+    pub source: Source,
+}
+
+impl<T> Defaulted<T> {
+    /// Create a value with where it came from.
+    pub fn new(value: T, source: Source) -> Self {
+        Defaulted { value, source }
+    }
+
+    /// Return true if the value is the built-in default.
+    pub fn is_default(&self) -> bool {
+        self.source == Source::BuiltinDefault
+    }
+
+    /// The path of the file the value was read from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.source {
+            Source::File(path) => Some(path),
+            Source::BuiltinDefault => None,
+        }
+    }
+
+    /// Convert the value, keeping where it came from.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Defaulted<U> {
+        Defaulted::new(f(self.value), self.source)
+    }
+
+    /// Get the value, forgetting where it came from.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Defaulted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Where loaded content came from.
+///
+/// This is displayed as the path, or as `built-in default`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The content was read from a file.
+    File(PathBuf),
+    /// The file did not exist, so the built-in default was used.
+    BuiltinDefault,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::File(path) => path.display().fmt(out),
+            Source::BuiltinDefault => out.write_str("built-in default"),
+        }
+    }
+}
+
+/// Load each of a list of paths that may not exist.
+///
+/// Each path is loaded by `load`, such as a call to
+/// [`std::fs::read_to_string`], and the outcome for each path is
+/// returned, in the same order.
+/// Errors are collected as outcomes, see [`LoadAllOptions`] to stop at
+/// the first error instead.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::load_all;
+/// use io_result_optional::outcome::Outcome;
+/// use std::fs;
+///
+/// let paths = ["plugins/a.toml", "plugins/b.toml"];
+/// let plugins = load_all(&paths, |path| fs::read_to_string(path));
+/// for plugin in plugins {
+///     match plugin.outcome {
+///         Outcome::Found(config) => { /* Load the plugin ... */ }
+///         Outcome::Missing => (),
+///         Outcome::Error(e) => eprintln!("Failed to read {}: {}", plugin.path.display(), e),
+///     }
+/// }
+/// ````
+#[track_caller]
+pub fn load_all<I, T, F>(paths: I, load: F) -> Vec<LoadOutcome<T>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+    F: FnMut(&Path) -> io::Result<T>,
+{
+    // Without fail_fast, loading never fails.
+    LoadAllOptions::new().load(paths, load).unwrap_or_default()
+}
+
+/// Options for [`load_all`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoadAllOptions {
+    fail_fast: bool,
+}
+
+impl LoadAllOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set if loading should stop at the first error, and return it.
+    /// By default, all paths are loaded and errors are collected as
+    /// outcomes.
+    pub fn fail_fast(mut self, value: bool) -> Self {
+        self.fail_fast = value;
+        self
+    }
+
+    /// Load each of `paths` with `load`.
+    ///
+    /// With [`fail_fast`](Self::fail_fast), the first error is
+    /// returned, otherwise this always succeeds.
+    #[track_caller]
+    pub fn load<I, T, F>(&self, paths: I, mut load: F) -> io::Result<Vec<LoadOutcome<T>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+        F: FnMut(&Path) -> io::Result<T>,
+    {
+        let caller = Location::caller();
+        let mut outcomes = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            let timestamp = SystemTime::now();
+            let start = Instant::now();
+            let result = timed("load_all", path, || {
+                optional_at(load(path), &NOT_FOUND, caller)
+            });
+            let duration = start.elapsed();
+            let outcome = match result {
+                Err(e) if self.fail_fast => return Err(e),
+                result => Outcome::from(result),
+            };
+            outcomes.push(LoadOutcome {
+                path: path.into(),
+                operation: "load_all",
+                outcome,
+                timestamp,
+                duration,
+            });
+        }
+        Ok(outcomes)
+    }
+}
+
+/// Open many files that may not exist, a few at a time.
+///
+/// At most `max_concurrent` files are opened at the same time, on as
+/// many threads, and opens that fail because the process is out of file
+/// descriptors are retried, see [`OpenManyOptions`].
+/// The outcomes are yielded in the order the opens complete, and a file
+/// that does not exist is an [`Outcome::Missing`] rather than an error.
+/// Opening goes on ahead of the iteration, so up to twice
+/// `max_concurrent` files may be open and not yet yielded.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::open_many_optional;
+/// use io_result_optional::outcome::Outcome;
+///
+/// let paths = (0..1000).map(|i| format!("data/{:04}.json", i));
+/// for opened in open_many_optional(paths, 16) {
+///     match opened.outcome {
+///         Outcome::Found(file) => { /* Read the file ... */ }
+///         Outcome::Missing => (),
+///         Outcome::Error(e) => eprintln!("Failed to open {}: {}", opened.path.display(), e),
+///     }
+/// }
+/// ````
+#[track_caller]
+pub fn open_many_optional<I>(paths: I, max_concurrent: usize) -> OpenMany
+where
+    I: IntoIterator,
+    I::Item: Into<PathBuf>,
+{
+    OpenManyOptions::new(max_concurrent).open(paths)
+}
+
+/// Options for [`open_many_optional`].
+#[derive(Clone, Debug)]
+pub struct OpenManyOptions {
+    max_concurrent: usize,
+    retry: RetryPolicy,
+}
+
+impl OpenManyOptions {
+    /// Create options for opening at most `max_concurrent` files at the
+    /// same time.
+    ///
+    /// By default, opens are retried as given by
+    /// [`RetryPolicy::fd_exhausted`].
+    pub fn new(max_concurrent: usize) -> Self {
+        OpenManyOptions {
+            max_concurrent: max_concurrent.max(1),
+            retry: RetryPolicy::fd_exhausted(),
+        }
+    }
+
+    /// Set how failed opens are retried.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Start opening each of `paths`.
+    #[track_caller]
+    pub fn open<I>(&self, paths: I) -> OpenMany
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        let paths = paths.into_iter().map(Into::into).collect::<Vec<PathBuf>>();
+        let threads = self.max_concurrent.min(paths.len());
+        let paths = Arc::new(Mutex::new(paths.into_iter()));
+        // Up to `threads` outcomes are buffered, and a thread with an
+        // outcome waits for room in the buffer before opening the next
+        // file, so no more than twice max_concurrent files are open and
+        // not yet yielded.
+        let (sender, outcomes) = sync_channel(threads);
+        let caller = Location::caller();
+        for _ in 0..threads {
+            let (paths, sender, retry) = (paths.clone(), sender.clone(), self.retry.clone());
+            thread::spawn(move || loop {
+                let next = paths.lock().unwrap_or_else(|e| e.into_inner()).next();
+                let Some(path) = next else { break };
+                let timestamp = SystemTime::now();
+                let start = Instant::now();
+                let result = timed("open_many_optional", &path, || {
+                    let file = retry.run(|| eintr(|| File::open(&path)));
+                    optional_at(file, &NOT_FOUND, caller)
+                });
+                let opened = LoadOutcome {
+                    path,
+                    operation: "open_many_optional",
+                    outcome: Outcome::from(result),
+                    timestamp,
+                    duration: start.elapsed(),
+                };
+                if sender.send(opened).is_err() {
+                    break;
+                }
+            });
+        }
+        OpenMany { outcomes }
+    }
+}
+
+/// An iterator over the outcomes of opening many files.
+///
+/// This is returned by [`open_many_optional`].
+/// When this is dropped, the files that are still left are not opened.
+#[derive(Debug)]
+pub struct OpenMany {
+    outcomes: Receiver<LoadOutcome<File>>,
+}
+
+impl Iterator for OpenMany {
+    type Item = LoadOutcome<File>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.outcomes.recv().ok()
+    }
+}
+
+/// A reader for a file that may not exist, that is not opened until
+/// it is first read.
+///
+/// If the file does not exist at the first read, this reader is empty.
+/// Any other error opening the file is returned from that read, and
+/// opening is attempted again at the next read.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::LazyOptionalFile;
+/// use std::io::Read;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let mut input = LazyOptionalFile::new("header.txt").chain(LazyOptionalFile::new("body.txt"));
+/// // Nothing is opened until now.
+/// let mut text = String::new();
+/// input.read_to_string(&mut text)?;
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Debug)]
+pub struct LazyOptionalFile {
+    state: LazyState,
+    caller: &'static Location<'static>,
+}
+
+#[derive(Debug)]
+enum LazyState {
+    Pending(PathBuf),
+    Open(File),
+    Missing,
+}
+
+impl LazyOptionalFile {
+    /// Create a reader for `path`, without opening it.
+    #[track_caller]
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        LazyOptionalFile {
+            state: LazyState::Pending(path.into()),
+            // Reads happen wherever the reader is passed, so a missing
+            // file is recorded where the reader was created.
+            caller: Location::caller(),
+        }
+    }
+}
+
+impl Read for LazyOptionalFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let LazyState::Pending(path) = &self.state {
+            let caller = self.caller;
+            self.state = match timed("LazyOptionalFile::read", path, || {
+                optional_at(eintr(|| File::open(path)), &NOT_FOUND, caller)
+            })? {
+                Some(file) => LazyState::Open(file),
+                None => LazyState::Missing,
+            };
+        }
+        match &mut self.state {
+            LazyState::Open(file) => file.read(buf),
+            _ => Ok(0),
+        }
+    }
+}
+
+/// Update a file that may not exist, atomically.
+///
+/// The current content of the file, or `None` if it does not exist,
+/// is given to `update`, which returns the new content.
+/// The new content is written to a temporary file in the same
+/// directory, which is then renamed to `path`, so `path` always has
+/// either the old or the new content, even if the program crashes
+/// in the middle of the update.
+/// Any missing parent directory is created.
+///
+/// If the file existed, its permissions are kept.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::fs::update_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// # let path = dir.path().join("state").join("runs");
+/// update_optional(&path, |old| {
+///     let runs: u64 = match old {
+///         Some(old) => String::from_utf8_lossy(&old).trim().parse().unwrap_or(0),
+///         None => 0,
+///     };
+///     Ok(format!("{}\n", runs + 1).into_bytes())
+/// })?;
+/// # assert_eq!(std::fs::read_to_string(&path)?, "1\n");
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn update_optional<P, F>(path: P, update: F) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    F: FnOnce(Option<Vec<u8>>) -> io::Result<Vec<u8>>,
+{
+    let (path, caller) = (path.as_ref(), Location::caller());
+    let old = timed("update_optional", path, || {
+        optional_at(eintr(|| fs::read(path)), &NOT_FOUND, caller)
+    })?;
+    let existed = old.is_some();
+    let new = update(old)?;
+    if !existed {
+        create_parent(path)?;
+    }
+    let tmp = temp_sibling(path);
+    let write = || -> io::Result<()> {
+        let mut file = eintr(|| File::options().write(true).create_new(true).open(&tmp))?;
+        if let Some(meta) = probe(eintr(|| fs::metadata(path)), &NOT_FOUND)? {
+            file.set_permissions(meta.permissions())?;
+        }
+        file.write_all(&new)?;
+        eintr(|| file.sync_all())?;
+        eintr(|| fs::rename(&tmp, path))
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Create the parent directory of `path`, if needed.
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => eintr(|| fs::create_dir_all(parent)),
+        _ => Ok(()),
+    }
+}
+
+/// Get a path next to `path`, for a temporary file to be renamed to
+/// `path`.
+/// The name is unique within the process.
+fn temp_sibling(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    ));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IoResultOptional;
+    use std::path::Path;
+
+    #[test]
+    fn retry_interrupted() {
+        let mut calls = 0;
+        let mut interrupted = || {
+            calls += 1;
+            if calls < 3 {
+                Err(io::ErrorKind::Interrupted.into())
+            } else {
+                Ok(calls)
+            }
+        };
+        assert_eq!(eintr_with(true, false, &mut interrupted).unwrap(), 3);
+        calls = 0;
+        let result = eintr_with(false, false, || -> io::Result<()> {
+            calls += 1;
+            Err(io::ErrorKind::Interrupted.into())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn nfs_quirks() {
+        let mut calls = 0;
+        let mut stale = || -> io::Result<()> {
+            calls += 1;
+            Err(io::ErrorKind::StaleNetworkFileHandle.into())
+        };
+        assert!(eintr_with(true, false, &mut stale).optional().is_err());
+        let result = eintr_with(true, true, &mut stale).optional();
+        assert!(result.unwrap().is_none());
+        assert_eq!(calls, 1 + 1 + NFS_RETRIES);
+    }
+
+    #[test]
+    fn lines_existing() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let first = lines_optional(&path).unwrap().next().unwrap().unwrap();
+        assert_eq!(first, "[package]");
+    }
+
+    #[test]
+    fn lines_non_existing_empty() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("nosuch.file");
+        assert_eq!(lines_optional(&path).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn set_permissions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        let perm = fs::metadata(dir.path()).unwrap().permissions();
+        assert!(!set_permissions_optional(&path, perm.clone()).unwrap());
+        File::create(&path).unwrap();
+        assert!(set_permissions_optional(&path, perm).unwrap());
+    }
+
+    #[test]
+    fn set_len() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        assert!(!truncate_if_exists(&path).unwrap());
+        assert!(!path.exists());
+        fs::write(&path, "some data").unwrap();
+        assert!(set_len_optional(&path, 4).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "some");
+        assert!(truncate_if_exists(&path).unwrap());
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn copy_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        let mut out = Vec::new();
+        assert_eq!(copy_optional_to(&path, &mut out).unwrap(), None);
+        fs::write(&path, "data").unwrap();
+        assert_eq!(copy_optional_to(&path, &mut out).unwrap(), Some(4));
+        assert_eq!(out, b"data");
+    }
+
+    #[test]
+    fn move_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (src, dst) = (dir.path().join("src"), dir.path().join("dst"));
+        assert_eq!(move_file(&src, &dst).unwrap(), None);
+        fs::write(&src, "data").unwrap();
+        assert_eq!(move_file(&src, &dst).unwrap(), Some(MoveOutcome::Renamed));
+        assert!(!src.exists());
+        fs::write(&src, "new").unwrap();
+        copy_replace(&src, &dst).unwrap();
+        assert_eq!(fs::read_to_string(&dst).unwrap(), "new");
+        let err = move_file(&src, dir.path().join("nosuch").join("dst")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        fs::remove_file(&src).unwrap();
+        let _strict = crate::strict::thread_strict_scope(true);
+        let err = move_file(&src, &dst).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn best_effort() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        assert!(write_best_effort(&path, "data").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "data");
+        let result: io::Result<()> = Err(io::ErrorKind::ReadOnlyFilesystem.into());
+        assert!(result.optional_read_only().unwrap().is_none());
+    }
+
+    #[test]
+    fn sync() {
+        let file = tempfile::tempfile().unwrap();
+        assert!(sync_best_effort(&file).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sync_pipe() {
+        use std::os::fd::OwnedFd;
+        let (_reader, writer) = io::pipe().unwrap();
+        let pipe = File::from(OwnedFd::from(writer));
+        assert!(!sync_best_effort(&pipe).unwrap());
+    }
+
+    #[test]
+    fn open_rw() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        assert!(open_rw_optional(&path).unwrap().is_none());
+        assert!(!path.exists());
+        fs::write(&path, "abc").unwrap();
+        let mut file = open_rw_optional(&path).unwrap().unwrap();
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        file.write_all(b"def").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abcdef");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn nofollow() {
+        let dir = tempfile::tempdir().unwrap();
+        let (file, link) = (dir.path().join("file"), dir.path().join("link"));
+        let options = File::options().read(true).clone();
+        assert!(matches!(
+            open_nofollow(&file, &options).unwrap(),
+            NoFollow::Missing
+        ));
+        fs::write(&file, "data").unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        assert!(matches!(
+            open_nofollow(&file, &options).unwrap(),
+            NoFollow::File(_)
+        ));
+        assert!(matches!(
+            open_nofollow(&link, &options).unwrap(),
+            NoFollow::Symlink
+        ));
+    }
+
+    #[test]
+    fn can_read_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        assert_eq!(can_read(&path).unwrap(), None);
+        assert_eq!(can_write(&path).unwrap(), None);
+        assert!(!path.exists());
+        fs::write(&path, "data").unwrap();
+        assert_eq!(can_read(&path).unwrap(), Some(true));
+        assert_eq!(can_write(&path).unwrap(), Some(true));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "data");
+    }
+
+    #[test]
+    fn create_parents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a").join("b").join("file");
+        create_with_parents(&path)
+            .unwrap()
+            .write_all(b"one")
+            .unwrap();
+        create_with_parents(&path)
+            .unwrap()
+            .write_all(b"two")
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn chmod() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        assert!(!chmod_if_exists(&path, 0o600).unwrap());
+        File::create(&path).unwrap();
+        assert!(chmod_if_exists(&path, 0o600).unwrap());
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn hard_link_tolerances() {
+        let dir = tempfile::tempdir().unwrap();
+        let (src, dst) = (dir.path().join("src"), dir.path().join("dst"));
+        assert!(hard_link_optional(&src, &dst).unwrap().is_none());
+        let err = HardLinkOptions::new().link(&src, &dst).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        {
+            let _strict = crate::strict::thread_strict_scope(true);
+            let err = hard_link_optional(&src, &dst).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        }
+        fs::write(&src, "data").unwrap();
+        let result = hard_link_optional(&src, &dst).unwrap();
+        assert_eq!(result, Some(HardLinkOutcome::Linked));
+        let result = hard_link_optional(&src, &dst).unwrap();
+        assert_eq!(result, Some(HardLinkOutcome::AlreadyLinked));
+    }
+
+    #[test]
+    fn hard_link_different_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let (src, dst) = (dir.path().join("src"), dir.path().join("dst"));
+        fs::write(&src, "data").unwrap();
+        fs::write(&dst, "same").unwrap();
+        let err = hard_link_optional(&src, &dst).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        fs::write(&dst, "data").unwrap();
+        let result = hard_link_optional(&src, &dst).unwrap();
+        assert_eq!(result, Some(HardLinkOutcome::AlreadyLinked));
+    }
+
+    #[test]
+    fn dir_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        assert!(dir_size_optional(&root).unwrap().is_none());
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a"), "12345").unwrap();
+        fs::write(root.join("sub").join("b"), "123").unwrap();
+        let size = dir_size_optional(&root).unwrap().unwrap();
+        assert_eq!(size.bytes, 8);
+        assert!(size.errors.is_empty());
+    }
+
+    #[test]
+    fn canonicalize() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = fs::canonicalize(dir.path()).unwrap();
+        let path = dir.path().join("a").join("b");
+        assert!(canonicalize_optional(&path).unwrap().is_none());
+        assert_eq!(
+            canonicalize_lenient(&path).unwrap(),
+            base.join("a").join("b")
+        );
+        fs::create_dir(dir.path().join("a")).unwrap();
+        let path = dir.path().join("a").join("..").join("c");
+        assert_eq!(canonicalize_lenient(&path).unwrap(), base.join("c"));
+        assert_eq!(canonicalize_lenient(dir.path()).unwrap(), base);
+    }
+
+    #[test]
+    fn load_or_embedded_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conf");
+        let content = load_or_embedded(&path, b"default").unwrap();
+        assert_eq!(&content[..], b"default");
+        assert!(content.is_default());
+        fs::write(&path, "custom").unwrap();
+        let content = load_or_embedded(&path, b"default").unwrap();
+        assert_eq!(&content[..], b"custom");
+        assert_eq!(content.source, Source::File(path));
+    }
+
+    #[test]
+    fn set_times() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stamp");
+        assert!(!touch_if_exists(&path).unwrap());
+        assert!(!path.exists());
+        fs::write(&path, "").unwrap();
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1 << 30);
+        assert!(set_times_optional(&path, FileTimes::new().set_modified(old)).unwrap());
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), old);
+        assert!(touch_if_exists(&path).unwrap());
+        assert!(fs::metadata(&path).unwrap().modified().unwrap() > old);
+    }
+
+    #[test]
+    fn prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        fs::create_dir_all(root.join("d/e")).unwrap();
+        fs::write(root.join("d/file"), "").unwrap();
+        assert_eq!(prune_empty_dirs(root).unwrap(), 4);
+        assert!(!root.join("a").exists());
+        assert!(root.join("d/file").exists());
+        assert_eq!(prune_empty_dirs(root.join("nosuch")).unwrap(), 0);
+        let _strict = crate::strict::thread_strict_scope(true);
+        fs::create_dir_all(root.join("f/g")).unwrap();
+        assert_eq!(prune_empty_dirs(root).unwrap(), 2);
+        assert_eq!(prune_empty_dirs(root.join("nosuch")).unwrap(), 0);
+        assert_eq!(
+            remove_empty_dir(&root.join("f")).unwrap(),
+            DirRemoval::Missing
+        );
+    }
+
+    #[test]
+    fn remove_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("file"), "").unwrap();
+        assert_eq!(remove_dir_if_empty(&sub).unwrap(), DirRemoval::NotEmpty);
+        assert!(remove_dir_if_empty(sub.join("file")).is_err());
+        fs::remove_file(sub.join("file")).unwrap();
+        assert_eq!(remove_dir_if_empty(&sub).unwrap(), DirRemoval::Removed);
+        assert_eq!(remove_dir_if_empty(&sub).unwrap(), DirRemoval::Missing);
+        let _strict = crate::strict::thread_strict_scope(true);
+        let missing = remove_dir_if_empty(&sub).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn open_or_memory_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("asset");
+        let mut content = String::new();
+        let mut reader = open_or_memory(&path, b"default").unwrap();
+        assert!(reader.is_memory());
+        reader.seek(SeekFrom::Start(3)).unwrap();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "ault");
+        fs::write(&path, "custom").unwrap();
+        let mut reader = open_or_memory(&path, b"default").unwrap();
+        assert!(!reader.is_memory());
+        content.clear();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "custom");
+    }
+
+    #[test]
+    fn load_or_default_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("count");
+        let count = load_or_default(&path, |p| fs::read_to_string(p)).unwrap();
+        assert_eq!((count.as_str(), count.path()), ("", None));
+        assert_eq!(count.source.to_string(), "built-in default");
+        fs::write(&path, "17").unwrap();
+        let count = load_or_default(&path, |p| fs::read_to_string(p)).unwrap();
+        assert_eq!(count.map(|c| c.parse::<u8>().unwrap()).into_inner(), 17);
+    }
+
+    #[test]
+    fn load_several() {
+        let dir = tempfile::tempdir().unwrap();
+        let found = dir.path().join("found");
+        fs::write(&found, "data").unwrap();
+        let paths = [found, dir.path().join("missing"), dir.path().into()];
+        let outcomes = load_all(&paths, |path| fs::read_to_string(path));
+        assert_eq!(outcomes.len(), 3);
+        assert!(matches!(&outcomes[0].outcome, Outcome::Found(data) if data == "data"));
+        assert!(outcomes[1].outcome.is_missing());
+        assert!(outcomes[2].outcome.is_error());
+        let result = LoadAllOptions::new()
+            .fail_fast(true)
+            .load(&paths, |path| fs::read_to_string(path));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn read_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("text");
+        assert_eq!(read_to_string_lossy_optional(&path).unwrap(), None);
+        fs::write(&path, b"caf\xe9").unwrap();
+        assert_eq!(
+            read_to_string_lossy_optional(&path).unwrap().as_deref(),
+            Some("caf\u{fffd}")
+        );
+        assert_eq!(
+            read_to_string_optional(&path, InvalidUtf8::Skip).unwrap(),
+            None
+        );
+        let error = read_to_string_optional(&path, InvalidUtf8::Fail).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn read_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache");
+        let valid = |data: &[u8]| data.starts_with(b"v2");
+        assert_eq!(read_cached_optional(&path, valid).unwrap(), None);
+        fs::write(&path, "v2 data").unwrap();
+        assert_eq!(
+            read_cached_optional(&path, valid).unwrap().as_deref(),
+            Some(&b"v2 data"[..])
+        );
+        fs::write(&path, "v1 data").unwrap();
+        assert_eq!(read_cached_optional(&path, valid).unwrap(), None);
+        assert!(path.exists());
+        let options = ReadCachedOptions::new().remove_invalid(true);
+        assert_eq!(options.read(&path, valid).unwrap(), None);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn open_many() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = (0..20)
+            .map(|i| dir.path().join(i.to_string()))
+            .collect::<Vec<_>>();
+        for path in paths.iter().step_by(2) {
+            fs::write(path, "").unwrap();
+        }
+        let mut outcomes = open_many_optional(paths.iter().chain(Some(&paths[0].join("sub"))), 3)
+            .map(|opened| opened.outcome)
+            .collect::<Vec<_>>();
+        assert_eq!(outcomes.len(), 21);
+        outcomes.retain(|outcome| !outcome.is_missing());
+        assert_eq!(outcomes.iter().filter(|o| o.is_found()).count(), 10);
+        assert_eq!(outcomes.iter().filter(|o| o.is_error()).count(), 1);
+    }
+
+    #[test]
+    fn open_many_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = (0..20)
+            .map(|i| dir.path().join(i.to_string()))
+            .collect::<Vec<_>>();
+        for path in &paths {
+            fs::write(path, "").unwrap();
+        }
+        let opens = open_many_optional(&paths, 2);
+        // Let the threads open what they can while nothing is taken.
+        thread::sleep(Duration::from_millis(200));
+        let held = SystemTime::now();
+        let outcomes = opens.collect::<Vec<_>>();
+        assert_eq!(outcomes.len(), 20);
+        let opened_while_held = outcomes.iter().filter(|o| o.timestamp < held).count();
+        assert!(
+            (1..=4).contains(&opened_while_held),
+            "{}",
+            opened_while_held
+        );
+    }
+
+    #[test]
+    fn lazy_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        let mut lazy = LazyOptionalFile::new(&path);
+        let mut early = LazyOptionalFile::new(&path);
+        assert_eq!(early.read(&mut [0; 8]).unwrap(), 0);
+        fs::write(&path, "data").unwrap();
+        let mut content = String::new();
+        lazy.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "data");
+        assert_eq!(early.read(&mut [0; 8]).unwrap(), 0);
+    }
+
+    #[test]
+    fn update() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sub").join("file");
+        let append = |old: Option<Vec<u8>>| {
+            let mut data = old.unwrap_or_else(|| b"start".to_vec());
+            data.extend_from_slice(b"+");
+            Ok(data)
+        };
+        update_optional(&path, append).unwrap();
+        update_optional(&path, append).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "start++");
+        let result = update_optional(&path, |_| Err(io::ErrorKind::InvalidData.into()));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_three_ways() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("link");
+        let result = symlink_tolerant("a", &link, false).unwrap();
+        assert_eq!(result, SymlinkOutcome::Created);
+        let result = symlink_tolerant("a", &link, false).unwrap();
+        assert_eq!(result, SymlinkOutcome::AlreadyPresent);
+        let err = symlink_tolerant("b", &link, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let result = symlink_tolerant("b", &link, true).unwrap();
+        assert_eq!(result, SymlinkOutcome::Replaced("a".into()));
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("b"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_never_replaces_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("file");
+        File::create(&link).unwrap();
+        let err = symlink_tolerant("a", &link, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(link.is_file());
+    }
+}
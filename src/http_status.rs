@@ -0,0 +1,84 @@
+//! HTTP status codes for io errors.
+//!
+//! This is for web services serving files, that need to answer with
+//! a status for each io error, such as 404 for a missing file.
+//!
+//! This module is available with the `http-status` feature.
+//! With the `http` feature, `to_status_code` gives an
+//! `http::StatusCode`.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::http_status::to_http_status;
+//! use std::fs::File;
+//!
+//! let error = File::open("public/nosuch.html").unwrap_err();
+//! assert_eq!(to_http_status(&error), 404);
+//! ````
+use crate::kinds::{is_fd_exhausted, MISSING_KINDS, STORAGE_FULL_KINDS, TRANSIENT_KINDS};
+use std::io::{self, ErrorKind};
+
+/// Get the HTTP status code for `error`.
+///
+/// * Missing things, see [`MISSING_KINDS`], give 404 Not Found.
+/// * `PermissionDenied` gives 403 Forbidden.
+/// * `InvalidInput` and `InvalidFilename` give 400 Bad Request.
+/// * `AlreadyExists` gives 409 Conflict.
+/// * `FileTooLarge` gives 413 Content Too Large.
+/// * `Unsupported` gives 501 Not Implemented.
+/// * `TimedOut` gives 504 Gateway Timeout.
+/// * Other errors that may go away if retried, see [`TRANSIENT_KINDS`]
+///   and [`is_fd_exhausted`], give 503 Service Unavailable.
+/// * Full storage, see [`STORAGE_FULL_KINDS`], gives 507 Insufficient
+///   Storage.
+/// * Anything else gives 500 Internal Server Error.
+pub fn to_http_status(error: &io::Error) -> u16 {
+    if is_fd_exhausted(error) {
+        return 503;
+    }
+    match error.kind() {
+        kind if MISSING_KINDS.contains(kind) => 404,
+        ErrorKind::PermissionDenied => 403,
+        ErrorKind::InvalidInput | ErrorKind::InvalidFilename => 400,
+        ErrorKind::AlreadyExists => 409,
+        ErrorKind::FileTooLarge => 413,
+        ErrorKind::Unsupported => 501,
+        ErrorKind::TimedOut => 504,
+        kind if TRANSIENT_KINDS.contains(kind) => 503,
+        kind if STORAGE_FULL_KINDS.contains(kind) => 507,
+        _ => 500,
+    }
+}
+
+/// Get the HTTP status code for `error`, see [`to_http_status`].
+///
+/// This is available with the `http` feature.
+#[cfg(feature = "http")]
+pub fn to_status_code(error: &io::Error) -> http::StatusCode {
+    http::StatusCode::from_u16(to_http_status(error))
+        .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_http_status;
+    use std::io::{self, ErrorKind};
+
+    #[test]
+    fn statuses() {
+        let status = |kind: ErrorKind| to_http_status(&io::Error::from(kind));
+        assert_eq!(status(ErrorKind::NotFound), 404);
+        assert_eq!(status(ErrorKind::PermissionDenied), 403);
+        assert_eq!(status(ErrorKind::TimedOut), 504);
+        assert_eq!(status(ErrorKind::ResourceBusy), 503);
+        assert_eq!(status(ErrorKind::StorageFull), 507);
+        assert_eq!(status(ErrorKind::InvalidData), 500);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn status_code() {
+        let error = io::Error::from(ErrorKind::NotFound);
+        assert_eq!(super::to_status_code(&error), http::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,290 @@
+//! Input that may be a file that does not exist, or standard input.
+//!
+//! Also output to a file, standard output, or nowhere.
+use crate::IoResultOptional;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// An input file that may not exist, or standard input.
+///
+/// This implements [`FromStr`], where `-` means standard input and
+/// anything else is a path, so it can be used directly as the type of
+/// a field in a struct deriving command line arguments, e.g. with
+/// `clap` or `argh`.
+/// The default is standard input.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::OptionalInput;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let input: OptionalInput = "words.txt".parse().unwrap();
+/// if let Some(words) = input.read_to_string()? {
+///     // Handle the words ...
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OptionalInput {
+    /// Standard input, given as `-`.
+    #[default]
+    Stdin,
+    /// A path to a file that may not exist.
+    Path(PathBuf),
+}
+
+impl OptionalInput {
+    /// Open the input.
+    ///
+    /// Return `Ok(None)` if the file does not exist.
+    /// Standard input always exists.
+    pub fn open(&self) -> io::Result<Option<Box<dyn Read>>> {
+        match self {
+            OptionalInput::Stdin => Ok(Some(Box::new(io::stdin()))),
+            OptionalInput::Path(path) => Ok(File::open(path)
+                .optional()?
+                .map(|file| Box::new(file) as Box<dyn Read>)),
+        }
+    }
+
+    /// Open the input as an [`Input`], that is [`Input::Absent`] if
+    /// the file does not exist.
+    pub fn input(&self) -> io::Result<Input> {
+        match self {
+            OptionalInput::Stdin => Ok(Input::Stdin(io::stdin())),
+            OptionalInput::Path(path) => Input::open_optional(path),
+        }
+    }
+
+    /// Read all of the input as a string.
+    ///
+    /// Return `Ok(None)` if the file does not exist.
+    pub fn read_to_string(&self) -> io::Result<Option<String>> {
+        match self.open()? {
+            Some(mut input) => {
+                let mut text = String::new();
+                input.read_to_string(&mut text)?;
+                Ok(Some(text))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The path of the input, or `None` for standard input.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            OptionalInput::Stdin => None,
+            OptionalInput::Path(path) => Some(path),
+        }
+    }
+}
+
+impl FromStr for OptionalInput {
+    type Err = EmptyPathError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "" => Err(EmptyPathError),
+            "-" => Ok(OptionalInput::Stdin),
+            path => Ok(OptionalInput::Path(path.into())),
+        }
+    }
+}
+
+impl fmt::Display for OptionalInput {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionalInput::Stdin => out.write_str("-"),
+            OptionalInput::Path(path) => path.display().fmt(out),
+        }
+    }
+}
+
+/// A reader that is a file, standard input, or nothing at all.
+///
+/// An absent input is read as empty.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::Input;
+/// use std::io::{self, BufRead, BufReader, Read};
+///
+/// fn count_lines(input: impl Read) -> io::Result<usize> {
+///     let mut count = 0;
+///     for line in BufReader::new(input).lines() {
+///         line?;
+///         count += 1;
+///     }
+///     Ok(count)
+/// }
+///
+/// # fn main() -> io::Result<()> {
+/// let input = Input::open_optional("nosuch.txt")?;
+/// assert!(input.is_absent());
+/// assert_eq!(count_lines(input)?, 0);
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Debug)]
+pub enum Input {
+    /// An opened file.
+    File(File),
+    /// Standard input.
+    Stdin(io::Stdin),
+    /// No input.
+    Absent,
+}
+
+impl Input {
+    /// Open a file that may not exist.
+    ///
+    /// If the file does not exist, the input is [`Input::Absent`].
+    pub fn open_optional<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(File::open(path)
+            .optional()?
+            .map_or(Input::Absent, Input::File))
+    }
+
+    /// Return true if this is [`Input::Absent`].
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Input::Absent)
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::File(file) => file.read(buf),
+            Input::Stdin(stdin) => stdin.read(buf),
+            Input::Absent => Ok(0),
+        }
+    }
+}
+
+/// A writer that is a file, standard output, or nothing at all.
+///
+/// Anything written to [`Output::Sink`] is discarded.
+#[derive(Debug)]
+pub enum Output {
+    /// A created file.
+    File(File),
+    /// Standard output.
+    Stdout(io::Stdout),
+    /// No output.
+    Sink,
+}
+
+impl Output {
+    /// Create the file `path`, or use standard output if `path` is `-`.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            Ok(Output::Stdout(io::stdout()))
+        } else {
+            File::create(path).map(Output::File)
+        }
+    }
+
+    /// Create the file `path`, if the directory for it exists.
+    ///
+    /// If the directory does not exist, the output is [`Output::Sink`].
+    /// This is useful for output that should only be written where it
+    /// has been asked for, such as `debug/trace.log`.
+    pub fn create_optional<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(File::create(path)
+            .optional()?
+            .map_or(Output::Sink, Output::File))
+    }
+
+    /// Return true if this is [`Output::Sink`].
+    pub fn is_sink(&self) -> bool {
+        matches!(self, Output::Sink)
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::File(file) => file.write(buf),
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::Sink => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::File(file) => file.flush(),
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::Sink => Ok(()),
+        }
+    }
+}
+
+/// The error for parsing an empty string as an [`OptionalInput`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmptyPathError;
+
+impl fmt::Display for EmptyPathError {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        out.write_str("the path must not be empty")
+    }
+}
+
+impl Error for EmptyPathError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, OptionalInput, Output};
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::path::Path;
+
+    #[test]
+    fn parse() {
+        assert_eq!("-".parse(), Ok(OptionalInput::Stdin));
+        let input: OptionalInput = "nosuch.file".parse().unwrap();
+        assert_eq!(input.path(), Some(Path::new("nosuch.file")));
+        assert!("".parse::<OptionalInput>().is_err());
+    }
+
+    #[test]
+    fn read() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let input = OptionalInput::Path(path);
+        assert!(input
+            .read_to_string()
+            .unwrap()
+            .unwrap()
+            .starts_with("[package]"));
+        let input = OptionalInput::Path("nosuch.file".into());
+        assert!(input.read_to_string().unwrap().is_none());
+    }
+
+    #[test]
+    fn input_absent() {
+        let input = OptionalInput::Path("nosuch.file".into()).input().unwrap();
+        assert!(input.is_absent());
+        let mut content = Vec::new();
+        Input::Absent.read_to_end(&mut content).unwrap();
+        assert!(content.is_empty());
+    }
+
+    #[test]
+    fn output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nosuch").join("file");
+        let mut output = Output::create_optional(&path).unwrap();
+        assert!(output.is_sink());
+        output.write_all(b"ignored").unwrap();
+        let path = dir.path().join("file");
+        let mut output = Output::create_optional(&path).unwrap();
+        output.write_all(b"data").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "data");
+    }
+}
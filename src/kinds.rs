@@ -0,0 +1,603 @@
+//! Sets of [`io::ErrorKind`]s, and curated groups of them.
+//!
+//! The groups are the crate's judgment about what kinds of errors mean
+//! that something is missing, that an operation may succeed if
+//! retried, or that retrying is pointless.
+//! Use them to configure tolerance or retries, rather than
+//! maintaining a list of your own.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::kinds::MISSING_KINDS;
+//! use io_result_optional::IoResultOptional;
+//! use std::fs::File;
+//! # use std::io;
+//!
+//! # fn main() -> io::Result<()> {
+//! if let Some(input) = File::open("data/input").optional_kinds(&MISSING_KINDS)? {
+//!     // The data exists, so handle it ...
+//! }
+//! # Ok(())
+//! # }
+//! ````
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+/// A set of [`io::ErrorKind`]s.
+///
+/// A set can be a constant, created by [`KindSet::new`], or built at
+/// runtime.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KindSet {
+    kinds: Cow<'static, [ErrorKind]>,
+}
+
+impl KindSet {
+    /// Create a set of the given kinds.
+    pub const fn new(kinds: &'static [ErrorKind]) -> Self {
+        KindSet {
+            kinds: Cow::Borrowed(kinds),
+        }
+    }
+
+    /// Return true if `kind` is in this set.
+    pub fn contains(&self, kind: ErrorKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+
+    /// Return true if the kind of `error` is in this set.
+    pub fn matches(&self, error: &io::Error) -> bool {
+        self.contains(error.kind())
+    }
+
+    /// Return this set with `kind` added.
+    pub fn with(mut self, kind: ErrorKind) -> Self {
+        if !self.contains(kind) {
+            self.kinds.to_mut().push(kind);
+        }
+        self
+    }
+
+    /// Return a set of all kinds that are in this set or in `other`.
+    pub fn union(&self, other: &KindSet) -> Self {
+        other.iter().fold(self.clone(), KindSet::with)
+    }
+
+    /// Iterate over the kinds in this set.
+    pub fn iter(&self) -> impl Iterator<Item = ErrorKind> + '_ {
+        self.kinds.iter().copied()
+    }
+
+    /// Return true if this set contains no kinds.
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+}
+
+impl FromIterator<ErrorKind> for KindSet {
+    fn from_iter<I: IntoIterator<Item = ErrorKind>>(iter: I) -> Self {
+        iter.into_iter().fold(KindSet::default(), KindSet::with)
+    }
+}
+
+/// Parse a comma-separated list of kinds and groups.
+///
+/// A kind is named like in [`io::ErrorKind`], ignoring case, dashes
+/// and underscores, so `permission-denied` is `PermissionDenied`.
+/// `timeout` is also accepted for `TimedOut`.
+/// The groups `missing`, `transient`, `fatal` and `storage-full` are
+/// [`MISSING_KINDS`], [`TRANSIENT_KINDS`], [`FATAL_KINDS`] and
+/// [`STORAGE_FULL_KINDS`].
+/// A name written exactly like a kind, such as `StorageFull`, is
+/// always just that kind, so a serialized set reads back the same.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::KindSet;
+/// use std::io::ErrorKind;
+///
+/// let kinds: KindSet = "notfound, permission-denied, timeout".parse().unwrap();
+/// assert!(kinds.contains(ErrorKind::PermissionDenied));
+/// assert!(kinds.contains(ErrorKind::TimedOut));
+/// ````
+impl FromStr for KindSet {
+    type Err = ParseKindError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut set = KindSet::default();
+        for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            if let Some(kind) = find_kind(|kind| kind == name) {
+                set = set.with(kind);
+                continue;
+            }
+            let key = name
+                .chars()
+                .filter(|c| !matches!(c, '-' | '_'))
+                .collect::<String>()
+                .to_lowercase();
+            let group = match key.as_str() {
+                "missing" => MISSING_KINDS,
+                "transient" => TRANSIENT_KINDS,
+                "fatal" => FATAL_KINDS,
+                "storagefull" => STORAGE_FULL_KINDS,
+                "timeout" => KindSet::new(&[ErrorKind::TimedOut]),
+                _ => find_kind(|kind| kind.to_lowercase() == key)
+                    .map(|kind| KindSet::default().with(kind))
+                    .ok_or_else(|| ParseKindError { name: name.into() })?,
+            };
+            set = set.union(&group);
+        }
+        Ok(set)
+    }
+}
+
+/// Find the kind with a name that `matches`, in [`ALL_KINDS`] and
+/// `Other`.
+fn find_kind(matches: impl Fn(&str) -> bool) -> Option<ErrorKind> {
+    ALL_KINDS
+        .iter()
+        .chain(Some(ErrorKind::Other))
+        .find(|kind| matches(&format!("{:?}", kind)))
+}
+
+/// An error parsing a [`KindSet`], for a name that is not a known
+/// kind or group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKindError {
+    name: String,
+}
+
+impl fmt::Display for ParseKindError {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        write!(out, "unknown error kind {:?}", self.name)
+    }
+}
+
+impl Error for ParseKindError {}
+
+/// With the `serde` feature, a set is serialized as a list of kind
+/// names, such as `["NotFound", "TimedOut"]`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for KindSet {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(self.iter().map(|kind| format!("{:?}", kind)))
+    }
+}
+
+/// With the `serde` feature, a set is deserialized from a list of
+/// kind and group names, or a string of comma-separated names, as
+/// parsed by [`KindSet::from_str`].
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for KindSet {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct Names;
+        impl<'de> serde::de::Visitor<'de> for Names {
+            type Value = KindSet;
+
+            fn expecting(&self, out: &mut fmt::Formatter) -> fmt::Result {
+                out.write_str("a list of error kinds")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<KindSet, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<KindSet, A::Error> {
+                let mut set = KindSet::default();
+                while let Some(name) = seq.next_element::<Cow<str>>()? {
+                    let kinds = name.parse::<KindSet>().map_err(serde::de::Error::custom)?;
+                    set = set.union(&kinds);
+                }
+                Ok(set)
+            }
+        }
+        d.deserialize_any(Names)
+    }
+}
+
+/// Kinds of errors meaning that the requested thing does not exist.
+///
+/// This is `NotFound`, and `NotADirectory`, which is what you get when
+/// trying to open `a/b` while `a` is a file.
+pub const MISSING_KINDS: KindSet = KindSet::new(&[ErrorKind::NotFound, ErrorKind::NotADirectory]);
+
+/// Just [`ErrorKind::NotFound`], the kinds that `optional()` tolerates.
+pub(crate) const NOT_FOUND: KindSet = KindSet::new(&[ErrorKind::NotFound]);
+
+/// Kinds of errors that may go away if the operation is retried.
+///
+/// These are interruptions, timeouts, things that are busy, and
+/// connections or networks that went away.
+pub const TRANSIENT_KINDS: KindSet = KindSet::new(&[
+    ErrorKind::Interrupted,
+    ErrorKind::WouldBlock,
+    ErrorKind::TimedOut,
+    ErrorKind::ResourceBusy,
+    ErrorKind::ConnectionReset,
+    ErrorKind::ConnectionAborted,
+    ErrorKind::NetworkDown,
+]);
+
+/// Kinds of errors that will not go away by retrying the operation.
+///
+/// These mean that the operation, as requested, is not allowed, not
+/// possible, or not meaningful.
+/// Something must be changed, by the program or an operator, before
+/// it can succeed.
+pub const FATAL_KINDS: KindSet = KindSet::new(&[
+    ErrorKind::PermissionDenied,
+    ErrorKind::InvalidInput,
+    ErrorKind::InvalidData,
+    ErrorKind::InvalidFilename,
+    ErrorKind::IsADirectory,
+    ErrorKind::ReadOnlyFilesystem,
+    ErrorKind::FileTooLarge,
+    ErrorKind::Unsupported,
+    ErrorKind::OutOfMemory,
+]);
+
+/// Kinds of errors meaning that there is no room left for writing.
+///
+/// This is `StorageFull` (`ENOSPC` on unix), and `QuotaExceeded`
+/// (`EDQUOT`).
+/// Best-effort writes, such as to a cache, may want to skip the write
+/// rather than fail.
+pub const STORAGE_FULL_KINDS: KindSet =
+    KindSet::new(&[ErrorKind::StorageFull, ErrorKind::QuotaExceeded]);
+
+/// Return true if `error` means that there is no room left for
+/// writing.
+///
+/// See [`STORAGE_FULL_KINDS`].
+pub fn is_storage_full(error: &io::Error) -> bool {
+    STORAGE_FULL_KINDS.matches(error)
+}
+
+/// Return true if `error` means that the process or system has run out
+/// of file descriptors, such as "too many open files".
+///
+/// This is `EMFILE` and `ENFILE` on unix, and `ERROR_TOO_MANY_OPEN_FILES`
+/// and `WSAEMFILE` on windows.
+/// These errors have no kind of their own, but are found by the os
+/// error code, also in an os error wrapped in another `io::Error`.
+/// They go away when other files are closed, so a retry after a while
+/// may succeed, see
+/// [`RetryPolicy::fd_exhausted`](crate::retry::RetryPolicy::fd_exhausted).
+pub fn is_fd_exhausted(error: &io::Error) -> bool {
+    match raw_os_error(error) {
+        #[cfg(unix)]
+        Some(code) => code == libc::EMFILE || code == libc::ENFILE,
+        #[cfg(windows)]
+        Some(code) => code == 4 || code == 10024,
+        _ => false,
+    }
+}
+
+/// Return true if `error` is a stale file handle, `ESTALE` on unix.
+///
+/// On a network file system such as NFS, this is what a file that is
+/// open, or was just looked up, gives when it has been removed or
+/// replaced by a rename on another host.
+/// Reopening the path gets the new file, if there is one.
+/// The os error code is also found wrapped in another `io::Error`.
+pub fn is_stale(error: &io::Error) -> bool {
+    if error.kind() == ErrorKind::StaleNetworkFileHandle {
+        return true;
+    }
+    #[cfg(unix)]
+    return raw_os_error(error) == Some(libc::ESTALE);
+    #[cfg(not(unix))]
+    return false;
+}
+
+/// Return true if `error` is one of the quirks of network file
+/// systems, that may go away if the operation is retried.
+///
+/// This is a [stale file handle](is_stale), and `WouldBlock`
+/// (`EAGAIN` on unix), which NFS may give when a lock or the server
+/// is busy.
+/// See [`fs::set_nfs_quirks`](crate::fs::set_nfs_quirks).
+pub fn is_nfs_quirk(error: &io::Error) -> bool {
+    is_stale(error) || error.kind() == ErrorKind::WouldBlock
+}
+
+/// All stable kinds of errors, except `Other`, in the order they are
+/// declared in [`io::ErrorKind`].
+///
+/// `Other` is left out since it is not a kind of its own, but a
+/// catch-all for errors that have no kind.
+pub const ALL_KINDS: KindSet = KindSet::new(&[
+    ErrorKind::NotFound,
+    ErrorKind::PermissionDenied,
+    ErrorKind::ConnectionRefused,
+    ErrorKind::ConnectionReset,
+    ErrorKind::HostUnreachable,
+    ErrorKind::NetworkUnreachable,
+    ErrorKind::ConnectionAborted,
+    ErrorKind::NotConnected,
+    ErrorKind::AddrInUse,
+    ErrorKind::AddrNotAvailable,
+    ErrorKind::NetworkDown,
+    ErrorKind::BrokenPipe,
+    ErrorKind::AlreadyExists,
+    ErrorKind::WouldBlock,
+    ErrorKind::NotADirectory,
+    ErrorKind::IsADirectory,
+    ErrorKind::DirectoryNotEmpty,
+    ErrorKind::ReadOnlyFilesystem,
+    ErrorKind::StaleNetworkFileHandle,
+    ErrorKind::InvalidInput,
+    ErrorKind::InvalidData,
+    ErrorKind::TimedOut,
+    ErrorKind::WriteZero,
+    ErrorKind::StorageFull,
+    ErrorKind::NotSeekable,
+    ErrorKind::QuotaExceeded,
+    ErrorKind::FileTooLarge,
+    ErrorKind::ResourceBusy,
+    ErrorKind::ExecutableFileBusy,
+    ErrorKind::Deadlock,
+    ErrorKind::CrossesDevices,
+    ErrorKind::TooManyLinks,
+    ErrorKind::InvalidFilename,
+    ErrorKind::ArgumentListTooLong,
+    ErrorKind::Interrupted,
+    ErrorKind::Unsupported,
+    ErrorKind::UnexpectedEof,
+    ErrorKind::OutOfMemory,
+]);
+
+/// A mapping of error kinds and raw os error codes to the kinds they
+/// should have.
+///
+/// Some libraries and file systems report errors with a kind that
+/// does not match what happened, such as `Other` for a file that is
+/// not found.
+/// A `KindMap` can normalize such errors before they are considered
+/// optional.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::{IoResultOptional, KindMap, MapKind};
+/// use std::io::{self, ErrorKind};
+///
+/// # fn third_party_open() -> io::Result<()> {
+/// #     Err(io::Error::other(io::Error::from_raw_os_error(2)))
+/// # }
+/// # fn main() -> io::Result<()> {
+/// let fix = KindMap::new()
+///     .kind(ErrorKind::InvalidInput, ErrorKind::NotFound)
+///     .raw_os_error(2, ErrorKind::NotFound);
+/// assert!(third_party_open().map_kinds(&fix).optional()?.is_none());
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KindMap {
+    kinds: Vec<(ErrorKind, ErrorKind)>,
+    codes: Vec<(i32, ErrorKind)>,
+}
+
+impl KindMap {
+    /// Create an empty map, that keeps all errors as they are.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map errors of kind `from` to kind `to`.
+    pub fn kind(mut self, from: ErrorKind, to: ErrorKind) -> Self {
+        self.kinds.push((from, to));
+        self
+    }
+
+    /// Map errors with the raw os error `code` to kind `to`.
+    ///
+    /// The code is also found in an os error wrapped in another
+    /// `io::Error`, such as `io::Error::other(os_error)`.
+    /// Codes are checked before kinds.
+    pub fn raw_os_error(mut self, code: i32, to: ErrorKind) -> Self {
+        self.codes.push((code, to));
+        self
+    }
+
+    /// Get the kind that `error` should have, if it is mapped.
+    pub fn target(&self, error: &io::Error) -> Option<ErrorKind> {
+        let code = raw_os_error(error);
+        let by_code = self.codes.iter().find(|(c, _)| Some(*c) == code);
+        let by_kind = || self.kinds.iter().find(|(from, _)| *from == error.kind());
+        by_code
+            .map(|(_, to)| *to)
+            .or_else(|| by_kind().map(|(_, to)| *to))
+    }
+
+    /// Give `error` the kind it should have.
+    ///
+    /// A remapped error keeps the message of the original error, which
+    /// becomes its source.
+    pub fn apply(&self, error: io::Error) -> io::Error {
+        match self.target(&error) {
+            Some(kind) if kind != error.kind() => io::Error::new(kind, error),
+            _ => error,
+        }
+    }
+}
+
+/// Get the raw os error code of `error`, or of an `io::Error` wrapped
+/// in it.
+fn raw_os_error(error: &io::Error) -> Option<i32> {
+    error.raw_os_error().or_else(|| {
+        error
+            .get_ref()?
+            .downcast_ref::<io::Error>()
+            .and_then(raw_os_error)
+    })
+}
+
+/// Remapping of the error kind in results.
+///
+/// Use this before [`optional`](crate::IoResultOptional::optional)
+/// to normalize errors that have the wrong kind.
+pub trait MapKind<T> {
+    /// Change the kind of an error of kind `from` to `to`.
+    fn map_kind(self, from: ErrorKind, to: ErrorKind) -> io::Result<T>;
+
+    /// Change the kind of an error with the raw os error `code` to `to`.
+    fn map_raw_os_error(self, code: i32, to: ErrorKind) -> io::Result<T>;
+
+    /// Change the kind of an error as given by `map`.
+    fn map_kinds(self, map: &KindMap) -> io::Result<T>;
+}
+
+impl<T> MapKind<T> for io::Result<T> {
+    fn map_kind(self, from: ErrorKind, to: ErrorKind) -> io::Result<T> {
+        self.map_kinds(&KindMap::new().kind(from, to))
+    }
+
+    fn map_raw_os_error(self, code: i32, to: ErrorKind) -> io::Result<T> {
+        self.map_kinds(&KindMap::new().raw_os_error(code, to))
+    }
+
+    fn map_kinds(self, map: &KindMap) -> io::Result<T> {
+        self.map_err(|e| map.apply(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_fd_exhausted, is_nfs_quirk, is_stale, is_storage_full, KindMap, KindSet, MapKind,
+        ALL_KINDS, FATAL_KINDS, MISSING_KINDS, STORAGE_FULL_KINDS, TRANSIENT_KINDS,
+    };
+    use std::io::{self, ErrorKind};
+
+    #[test]
+    fn groups_are_disjoint() {
+        let groups = [
+            &MISSING_KINDS,
+            &TRANSIENT_KINDS,
+            &FATAL_KINDS,
+            &STORAGE_FULL_KINDS,
+        ];
+        for (i, a) in groups.iter().enumerate() {
+            for b in &groups[i + 1..] {
+                assert!(a.iter().all(|kind| !b.contains(kind)));
+            }
+            assert!(a.iter().all(|kind| ALL_KINDS.contains(kind)));
+        }
+    }
+
+    #[test]
+    fn build_set() {
+        let set = KindSet::default()
+            .with(ErrorKind::NotFound)
+            .with(ErrorKind::TimedOut)
+            .with(ErrorKind::NotFound);
+        assert_eq!(set.iter().count(), 2);
+        assert!(set.contains(ErrorKind::TimedOut));
+        assert!(!set.contains(ErrorKind::PermissionDenied));
+        assert_eq!(set.union(&MISSING_KINDS).iter().count(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn storage_full() {
+        // ENOSPC
+        assert!(is_storage_full(&io::Error::from_raw_os_error(28)));
+        assert!(!is_storage_full(&io::ErrorKind::NotFound.into()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fd_exhausted() {
+        assert!(is_fd_exhausted(&io::Error::from_raw_os_error(libc::EMFILE)));
+        let wrapped = io::Error::other(io::Error::from_raw_os_error(libc::ENFILE));
+        assert!(is_fd_exhausted(&wrapped));
+        assert!(!is_fd_exhausted(&io::ErrorKind::NotFound.into()));
+    }
+
+    #[test]
+    fn nfs_quirks() {
+        let stale = io::Error::from(ErrorKind::StaleNetworkFileHandle);
+        assert!(is_stale(&stale));
+        assert!(is_nfs_quirk(&ErrorKind::WouldBlock.into()));
+        assert!(!is_nfs_quirk(&ErrorKind::NotFound.into()));
+        #[cfg(unix)]
+        assert!(is_stale(&io::Error::other(io::Error::from_raw_os_error(
+            libc::ESTALE
+        ))));
+    }
+
+    #[test]
+    fn parse() {
+        let kinds: KindSet = "NotFound,permission-denied, timeout ,transient"
+            .parse()
+            .unwrap();
+        assert!(kinds.contains(ErrorKind::NotFound));
+        assert!(kinds.contains(ErrorKind::PermissionDenied));
+        assert!(kinds.contains(ErrorKind::TimedOut));
+        assert!(kinds.contains(ErrorKind::ResourceBusy));
+        assert!(!kinds.contains(ErrorKind::NotADirectory));
+        assert_eq!("".parse(), Ok(KindSet::default()));
+        let error = "notfound,nosuch".parse::<KindSet>().unwrap_err();
+        assert_eq!(error.to_string(), "unknown error kind \"nosuch\"");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let kinds: KindSet = serde_json::from_str(r#"["not-found", "transient"]"#).unwrap();
+        assert_eq!(kinds, super::NOT_FOUND.union(&TRANSIENT_KINDS));
+        let kinds: KindSet = serde_json::from_str(r#""NotFound,TimedOut""#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&kinds).unwrap(),
+            r#"["NotFound","TimedOut"]"#
+        );
+        assert!(serde_json::from_str::<KindSet>(r#"["bogus"]"#).is_err());
+        let group: KindSet = serde_json::from_str(r#""storage-full""#).unwrap();
+        assert_eq!(group, STORAGE_FULL_KINDS);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        for kind in ALL_KINDS.iter() {
+            let set = KindSet::default().with(kind);
+            let json = serde_json::to_string(&set).unwrap();
+            assert_eq!(
+                serde_json::from_str::<KindSet>(&json).unwrap(),
+                set,
+                "{}",
+                json
+            );
+        }
+        let json = serde_json::to_string(&ALL_KINDS).unwrap();
+        assert_eq!(serde_json::from_str::<KindSet>(&json).unwrap(), ALL_KINDS);
+    }
+
+    #[test]
+    fn remap() {
+        let result: io::Result<()> = Err(io::Error::new(ErrorKind::InvalidInput, "bad path"));
+        let err = result
+            .map_kind(ErrorKind::InvalidInput, ErrorKind::NotFound)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "bad path");
+
+        let wrapped = io::Error::other(io::Error::from_raw_os_error(2));
+        let map = KindMap::new()
+            .kind(ErrorKind::Other, ErrorKind::TimedOut)
+            .raw_os_error(2, ErrorKind::NotFound);
+        assert_eq!(map.apply(wrapped).kind(), ErrorKind::NotFound);
+        let other = io::Error::other("other");
+        assert_eq!(map.apply(other).kind(), ErrorKind::TimedOut);
+        let denied = io::Error::from(ErrorKind::PermissionDenied);
+        assert_eq!(map.apply(denied).kind(), ErrorKind::PermissionDenied);
+    }
+}
@@ -35,7 +35,114 @@
 //! # Ok(())
 //! # }
 //! ````
+//!
+//! # Strict mode
+//!
+//! In [strict mode](strict), nothing is optional: every `optional()`
+//! conversion returns the original error instead of `Ok(None)`.
+//!
+//! # Features
+//!
+//! The following cargo features enable integration with other crates.
+//! None of them are enabled by default.
+//!
+//! * `audit`: The `audit` module, for a log of tolerated errors.
+//! * `clap`: The `clap` module, for optional input file arguments.
+//! * `csv`: The `csv` module, for reading optional csv files.
+//! * `http`: `http_status::to_status_code`, giving an `http::StatusCode`.
+//! * `http-status`: The `http_status` module, with HTTP status codes for
+//!   io errors.
+//! * `notify`: `watch::watch_optional`, using native notifications.
+//! * `object_store`: [`MaybeMissing`] for `object_store::Error`.
+//! * `opendal`: [`MaybeMissing`] for `opendal::Error`.
+//! * `proptest`: The `proptest` module, with strategies for io errors
+//!   and results.
+//! * `reqwest`: [`MaybeMissing`] for `reqwest::Error` with status 404.
+//! * `rusqlite`: [`MaybeMissing`] for `rusqlite::Error::QueryReturnedNoRows`.
+//! * `serde`: Serialization of `outcome::LoadOutcome`, and of
+//!   [`KindSet`], [`Tolerate`] and `retry::RetryPolicy`.
+//! * `sqlx`: [`MaybeMissing`] for `sqlx::Error::RowNotFound`.
+//! * `tar`: The `tar` module, for optional entries in tar archives.
+//! * `telemetry`: The `telemetry` module, for timing of file system
+//!   attempts in the `fs` module.
+//! * `tempfile`: The `tempfile` module, for falling back to a temporary
+//!   file when a file can not be created.
+//! * `test-util`: The `test_util` module, with helpers for testing how
+//!   errors are handled.
+//! * `tokio`: The `tokio` module, for opening optional files with tokio.
+//! * `unix-permissions`: The `permissions` module, explaining why
+//!   permission was denied.
+//! * `unix-xattr`: The `xattr` module, for optional extended attributes.
+//! * `ureq`: [`MaybeMissing`] for `ureq::Error` with status 404.
+//! * `zip`: The `zip` module, for optional zip archives and entries.
+use std::error::Error;
+use std::fmt;
 use std::io;
+use std::panic::Location;
+
+#[cfg(feature = "audit")]
+pub mod audit;
+mod cell;
+#[cfg(feature = "clap")]
+pub mod clap;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod dirs;
+mod error;
+pub mod exit;
+#[cfg(any(
+    feature = "object_store",
+    feature = "opendal",
+    feature = "reqwest",
+    feature = "rusqlite",
+    feature = "sqlx",
+    feature = "ureq",
+))]
+mod foreign;
+pub mod fs;
+#[cfg(feature = "http-status")]
+pub mod http_status;
+mod input;
+pub mod kinds;
+pub mod net;
+mod option;
+pub mod outcome;
+#[cfg(all(unix, feature = "unix-permissions"))]
+pub mod permissions;
+pub mod prelude;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+pub mod render;
+pub mod retry;
+pub mod rw;
+mod search_path;
+pub mod strict;
+#[cfg(feature = "tar")]
+pub mod tar;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "tempfile")]
+pub mod tempfile;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod timeout;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+mod tolerate;
+pub mod watch;
+#[cfg(all(unix, feature = "unix-xattr"))]
+pub mod xattr;
+#[cfg(feature = "zip")]
+pub mod zip;
+
+pub use cell::{OnceOptional, OptionalLazy};
+pub use error::{OptionalIoError, WithPath};
+pub use input::{EmptyPathError, Input, OptionalInput, Output};
+pub use kinds::{KindMap, KindSet, MapKind};
+pub use option::{IoOption, IoOptionExt, MissingOrError};
+pub use search_path::SearchPath;
+pub use timeout::with_timeout;
+pub use tolerate::Tolerate;
 
 /// A trait for [`io::Result`] that adds a method making it easy to
 /// tell the difference between a file not found and another error,
@@ -47,6 +154,10 @@ pub trait IoResultOptional<T> {
     /// If it is any other error, return it as-is,
     /// and if it is `Ok(value)` convert it to `Ok(Some(value))`.
     ///
+    /// For a result that is already an [`IoOption`], use
+    /// [`optional_flat`](IoOptionExt::optional_flat) to get an
+    /// `IoOption<T>` rather than an `IoOption<Option<T>>`.
+    ///
     /// # Examples
     /// ````
     /// use std::fs::File;
@@ -64,22 +175,386 @@ pub trait IoResultOptional<T> {
     /// # Ok(())
     /// # }
     /// ````
-    fn optional(self) -> io::Result<Option<T>>;
+    fn optional(self) -> IoOption<T>;
+
+    /// Consider the given file access optional, for a given set of
+    /// error kinds.
+    /// If the result is an error with a kind in `kinds`, convert it to
+    /// `Ok(None)`.
+    /// Otherwise, this works just like [`optional`](Self::optional).
+    ///
+    /// See the [`kinds`] module for some useful sets.
+    fn optional_kinds(self, kinds: &KindSet) -> IoOption<T>;
+
+    /// Consider the operation an optional capability.
+    /// If the result is an error with [`io::ErrorKind`] `Unsupported`,
+    /// convert it to `Ok(None)`.
+    /// If it is any other error (including `NotFound`), return it as-is,
+    /// and if it is `Ok(value)` convert it to `Ok(Some(value))`.
+    ///
+    /// # Examples
+    /// ````
+    /// use std::fs::File;
+    /// # use std::io;
+    /// use io_result_optional::IoResultOptional;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = File::open("Cargo.toml")?;
+    /// if file.sync_all().optional_unsupported()?.is_none() {
+    ///     // Syncing is not supported on this file system.
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ````
+    fn optional_unsupported(self) -> IoOption<T>;
+
+    /// Consider the operation optional if it takes too long.
+    /// If the result is an error with [`io::ErrorKind`] `TimedOut`,
+    /// convert it to `Ok(None)`.
+    /// If it is any other error (including `NotFound`), return it as-is,
+    /// and if it is `Ok(value)` convert it to `Ok(Some(value))`.
+    ///
+    /// This goes well with [`with_timeout`].
+    fn optional_timed_out(self) -> IoOption<T>;
+
+    /// Consider a write optional if there is no room for it.
+    /// If the result is an error that the storage is full, or that a
+    /// quota is exceeded, convert it to `Ok(None)`.
+    /// If it is any other error (including `NotFound`), return it as-is,
+    /// and if it is `Ok(value)` convert it to `Ok(Some(value))`.
+    ///
+    /// See [`kinds::STORAGE_FULL_KINDS`].
+    ///
+    /// # Examples
+    /// ````
+    /// use std::fs;
+    /// # use std::io;
+    /// use io_result_optional::IoResultOptional;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let path = dir.path().join("thumbnail.png");
+    /// # let data = b"";
+    /// if fs::write(&path, data).optional_storage_full()?.is_none() {
+    ///     // The disk is full, so this thumbnail is not cached.
+    ///     let _ = fs::remove_file(&path);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ````
+    fn optional_storage_full(self) -> IoOption<T>;
+
+    /// Consider a write optional if the file system is read-only.
+    /// If the result is an error with [`io::ErrorKind`]
+    /// `ReadOnlyFilesystem`, convert it to `Ok(None)`.
+    /// If it is any other error (including `NotFound`), return it as-is,
+    /// and if it is `Ok(value)` convert it to `Ok(Some(value))`.
+    ///
+    /// See also [`fs::write_best_effort`].
+    fn optional_read_only(self) -> IoOption<T>;
+
+    /// Consider the input optional if it is malformed.
+    /// If the result is an error with [`io::ErrorKind`] `InvalidData`,
+    /// convert it to `Ok(None)`.
+    /// If it is any other error (including `NotFound`), return it as-is,
+    /// and if it is `Ok(value)` convert it to `Ok(Some(value))`.
+    ///
+    /// To skip an input that is either missing or malformed, use
+    /// [`optional_kinds`](Self::optional_kinds) with a set including
+    /// both kinds.
+    ///
+    /// # Examples
+    /// ````
+    /// use io_result_optional::kinds::MISSING_KINDS;
+    /// use io_result_optional::IoResultOptional;
+    /// use std::fs;
+    /// use std::io::{self, ErrorKind};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let text = b"caf\xe9".to_vec();
+    /// let text = String::from_utf8(text)
+    ///     .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    ///     .optional_invalid_data()?;
+    /// assert_eq!(text, None);
+    ///
+    /// let stats = fs::read_to_string("stats.txt")
+    ///     .optional_kinds(&MISSING_KINDS.with(ErrorKind::InvalidData))?;
+    /// # Ok(())
+    /// # }
+    /// ````
+    fn optional_invalid_data(self) -> IoOption<T>;
+
+    /// Consider the given file access optional, even if the `NotFound`
+    /// error is wrapped in another error.
+    /// This works like [`optional`](Self::optional), but also converts
+    /// an error to `Ok(None)` if an error that it wraps, or any error in
+    /// its [`source`](std::error::Error::source) chain, is an
+    /// `io::Error` of kind `NotFound`, or an error that
+    /// [is missing](MaybeMissing::is_missing) of a type that this crate
+    /// implements [`MaybeMissing`] for.
+    ///
+    /// # Examples
+    /// ````
+    /// use io_result_optional::IoResultOptional;
+    /// use std::fs;
+    /// use std::io;
+    ///
+    /// fn read_through_layer(path: &str) -> io::Result<Vec<u8>> {
+    ///     fs::read(path).map_err(io::Error::other)
+    /// }
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// assert!(read_through_layer("nosuch.file").optional().is_err());
+    /// assert_eq!(read_through_layer("nosuch.file").optional_deep()?, None);
+    /// # Ok(())
+    /// # }
+    /// ````
+    fn optional_deep(self) -> IoOption<T>;
+
+    /// Consider the given file access optional, and add context to
+    /// any other error.
+    /// This works like [`optional`](Self::optional), but an error that
+    /// is returned gets `context` as a prefix of its message.
+    /// The kind of the error is kept, and the original error is its
+    /// [`source`](std::error::Error::source).
+    ///
+    /// # Examples
+    /// ````
+    /// use std::fs;
+    /// # use std::io;
+    /// use io_result_optional::IoResultOptional;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let config = fs::read_to_string(".app.rc").optional_with_context("reading .app.rc")?;
+    /// # Ok(())
+    /// # }
+    /// ````
+    fn optional_with_context<C: fmt::Display>(self, context: C) -> IoOption<T>;
 }
 
 impl<T> IoResultOptional<T> for io::Result<T> {
-    fn optional(self) -> io::Result<Option<T>> {
+    #[track_caller]
+    fn optional(self) -> IoOption<T> {
+        match self {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound && tolerate(Some(e)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[track_caller]
+    fn optional_kinds(self, kinds: &KindSet) -> IoOption<T> {
         match self {
             Ok(value) => Ok(Some(value)),
-            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(ref e) if kinds.matches(e) && tolerate(Some(e)) => Ok(None),
             Err(e) => Err(e),
         }
     }
+
+    #[track_caller]
+    fn optional_unsupported(self) -> IoOption<T> {
+        self.optional_kinds(&KindSet::new(&[io::ErrorKind::Unsupported]))
+    }
+
+    #[track_caller]
+    fn optional_timed_out(self) -> IoOption<T> {
+        self.optional_kinds(&KindSet::new(&[io::ErrorKind::TimedOut]))
+    }
+
+    #[track_caller]
+    fn optional_storage_full(self) -> IoOption<T> {
+        self.optional_kinds(&kinds::STORAGE_FULL_KINDS)
+    }
+
+    #[track_caller]
+    fn optional_read_only(self) -> IoOption<T> {
+        self.optional_kinds(&KindSet::new(&[io::ErrorKind::ReadOnlyFilesystem]))
+    }
+
+    #[track_caller]
+    fn optional_invalid_data(self) -> IoOption<T> {
+        self.optional_kinds(&KindSet::new(&[io::ErrorKind::InvalidData]))
+    }
+
+    #[track_caller]
+    fn optional_deep(self) -> IoOption<T> {
+        match self {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if is_missing_deep(e) && tolerate(Some(e)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[track_caller]
+    fn optional_with_context<C: fmt::Display>(self, context: C) -> IoOption<T> {
+        self.optional().map_err(|source| {
+            io::Error::new(
+                source.kind(),
+                ContextError {
+                    context: context.to_string(),
+                    source,
+                },
+            )
+        })
+    }
+}
+
+/// An error with a context message, from
+/// [`IoResultOptional::optional_with_context`].
+#[derive(Debug)]
+struct ContextError {
+    context: String,
+    source: io::Error,
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        write!(out, "{}: {}", self.context, self.source)
+    }
+}
+
+impl Error for ContextError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// An error type that can tell if it means that something is missing.
+///
+/// This is implemented for error types of other crates (with the
+/// corresponding features enabled), so that results with those errors
+/// get the [`ResultOptional::optional`] method.
+/// For [`io::Error`], use [`IoResultOptional`] instead.
+pub trait MaybeMissing {
+    /// Return true if this error means that the requested thing is
+    /// missing, rather than that something went wrong.
+    fn is_missing(&self) -> bool;
+}
+
+/// A trait for results with a [`MaybeMissing`] error, adding the
+/// same `optional()` method that [`IoResultOptional`] provides for
+/// [`io::Result`].
+pub trait ResultOptional<T, E> {
+    /// Consider the operation optional.
+    /// If the result is an error that [is missing](MaybeMissing::is_missing),
+    /// convert it to `Ok(None)`.
+    /// If it is any other error, return it as-is,
+    /// and if it is `Ok(value)` convert it to `Ok(Some(value))`.
+    fn optional(self) -> Result<Option<T>, E>;
+}
+
+impl<T, E: MaybeMissing> ResultOptional<T, E> for Result<T, E> {
+    #[track_caller]
+    fn optional(self) -> Result<Option<T>, E> {
+        match self {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if e.is_missing() && tolerate(None) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Check if `error`, an error it wraps, or an error in its source chain,
+/// means that something is missing.
+fn is_missing_deep(error: &(dyn Error + 'static)) -> bool {
+    if let Some(e) = error.downcast_ref::<io::Error>() {
+        // The source of an io::Error is the source of the error it
+        // wraps, so check the wrapped error itself.
+        return e.kind() == io::ErrorKind::NotFound
+            || e.get_ref().is_some_and(|inner| is_missing_deep(inner));
+    }
+    if let Some(e) = error.downcast_ref::<MissingOrError>() {
+        return e.is_missing() || error.source().is_some_and(is_missing_deep);
+    }
+    let checks: &[fn(&(dyn Error + 'static)) -> bool] = &[
+        #[cfg(feature = "object_store")]
+        missing_as::<object_store::Error>,
+        #[cfg(feature = "opendal")]
+        missing_as::<opendal::Error>,
+        #[cfg(feature = "reqwest")]
+        missing_as::<reqwest::Error>,
+        #[cfg(feature = "rusqlite")]
+        missing_as::<rusqlite::Error>,
+        #[cfg(feature = "sqlx")]
+        missing_as::<sqlx::Error>,
+        #[cfg(feature = "ureq")]
+        missing_as::<ureq::Error>,
+        #[cfg(feature = "zip")]
+        missing_as::<::zip::result::ZipError>,
+    ];
+    checks.iter().any(|check| check(error)) || error.source().is_some_and(is_missing_deep)
+}
+
+#[cfg(any(
+    feature = "object_store",
+    feature = "opendal",
+    feature = "reqwest",
+    feature = "rusqlite",
+    feature = "sqlx",
+    feature = "ureq",
+    feature = "zip",
+))]
+fn missing_as<E: Error + MaybeMissing + 'static>(error: &(dyn Error + 'static)) -> bool {
+    error.downcast_ref::<E>().is_some_and(E::is_missing)
+}
+
+/// Check if an error that would be converted to `Ok(None)` should be
+/// tolerated, or is returned because of [strict mode](strict).
+///
+/// Each error that is tolerated is recorded in the audit log, with the
+/// location of the caller, when the `audit` feature is enabled.
+#[track_caller]
+fn tolerate(error: Option<&io::Error>) -> bool {
+    tolerate_at(error, Location::caller())
+}
+
+/// Like [`tolerate`], but with the location to record given.
+///
+/// This is for helpers that check the error inside a closure, where
+/// the location of their caller is not known.
+pub(crate) fn tolerate_at(error: Option<&io::Error>, caller: &'static Location<'static>) -> bool {
+    if strict::is_strict() {
+        return false;
+    }
+    #[cfg(feature = "audit")]
+    audit::record(error, caller);
+    #[cfg(not(feature = "audit"))]
+    let _ = (error, caller);
+    true
+}
+
+/// Convert an error with a kind in `kinds` to `Ok(None)`, like
+/// [`optional_kinds`](IoResultOptional::optional_kinds), with the
+/// location to record given, see [`tolerate_at`].
+pub(crate) fn optional_at<T>(
+    result: io::Result<T>,
+    kinds: &KindSet,
+    caller: &'static Location<'static>,
+) -> IoOption<T> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ref e) if kinds.matches(e) && tolerate_at(Some(e), caller) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Convert an error with a kind in `kinds` to `Ok(None)`, regardless
+/// of strict mode.
+///
+/// This is for when this crate itself checks if something exists,
+/// rather than considering it optional.
+pub(crate) fn probe<T>(result: io::Result<T>, kinds: &KindSet) -> io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ref e) if kinds.matches(e) => Ok(None),
+        Err(e) => Err(e),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::IoResultOptional;
+    use crate::{IoResultOptional, MissingOrError, OptionalIoError};
+    use std::error::Error;
     use std::fs::File;
     use std::io;
     use std::path::Path;
@@ -87,7 +562,7 @@ mod tests {
     #[test]
     fn existing_some() {
         assert!(
-            File::open(&Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+            File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
                 .optional()
                 .unwrap()
                 .is_some()
@@ -97,19 +572,58 @@ mod tests {
     #[test]
     fn non_existing_none() {
         assert!(
-            File::open(&Path::new(env!("CARGO_MANIFEST_DIR")).join("nosuch.file"))
+            File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("nosuch.file"))
                 .optional()
                 .unwrap()
                 .is_none()
         )
     }
 
+    #[test]
+    fn unsupported_none() {
+        let result: io::Result<()> = Err(io::Error::from(io::ErrorKind::Unsupported));
+        assert!(result.optional_unsupported().unwrap().is_none());
+        let result: io::Result<()> = Err(io::Error::from(io::ErrorKind::NotFound));
+        assert!(result.optional_unsupported().is_err());
+    }
+
+    #[test]
+    fn deep() {
+        let wrapped =
+            io::Error::other(OptionalIoError::new(io::ErrorKind::NotFound.into(), "file"));
+        let result: io::Result<()> = Err(wrapped);
+        assert!(result.optional_deep().unwrap().is_none());
+        let missing = io::Error::other(MissingOrError::Missing);
+        assert!(Err::<(), _>(missing).optional_deep().unwrap().is_none());
+        let other = io::Error::other(io::Error::from(io::ErrorKind::TimedOut));
+        assert!(Err::<(), _>(other).optional_deep().is_err());
+    }
+
+    #[test]
+    fn invalid_data_none() {
+        let result: io::Result<()> = Err(io::Error::from(io::ErrorKind::InvalidData));
+        assert!(result.optional_invalid_data().unwrap().is_none());
+        let result: io::Result<()> = Err(io::Error::from(io::ErrorKind::NotFound));
+        assert!(result.optional_invalid_data().is_err());
+    }
+
     #[test]
     fn other_is_error() {
         let result: io::Result<()> = Err(io::Error::new(io::ErrorKind::TimedOut, "too slow"));
-        assert_eq!(
-            format!("{:?}", result.optional()),
-            "Err(Custom { kind: TimedOut, error: StringError(\"too slow\") })",
-        )
+        let err = result.optional().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(err.to_string(), "too slow");
+    }
+
+    #[test]
+    fn with_context() {
+        let result: io::Result<()> = Err(io::ErrorKind::NotFound.into());
+        assert!(result.optional_with_context("reading").unwrap().is_none());
+        let result: io::Result<()> = Err(io::Error::new(io::ErrorKind::TimedOut, "too slow"));
+        let err = result.optional_with_context("reading data").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(err.to_string(), "reading data: too slow");
+        let source = Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "too slow");
     }
 }
@@ -0,0 +1,97 @@
+//! Probing network services that may be down.
+use crate::{IoOption, IoResultOptional, KindSet};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// Kinds of errors meaning that nothing is listening at an address.
+///
+/// These are what connecting gives when the service is not running,
+/// or the host or network can not be reached in time.
+pub const DOWN_KINDS: KindSet = KindSet::new(&[
+    ErrorKind::ConnectionRefused,
+    ErrorKind::TimedOut,
+    ErrorKind::HostUnreachable,
+    ErrorKind::NetworkUnreachable,
+]);
+
+/// Connect to a service that may be down, waiting at most `timeout`.
+///
+/// Return `Ok(Some(stream))` if the connection is made, and `Ok(None)`
+/// if it is refused, times out, or the host or network is unreachable,
+/// see [`DOWN_KINDS`].
+/// Any other error, such as a zero `timeout`, is returned.
+/// See [`ConnectOptions`] to choose which errors mean that the service
+/// is down.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::net::connect_optional_timeout;
+/// use std::time::Duration;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let addr = "127.0.0.1:1".parse().unwrap();
+/// if connect_optional_timeout(&addr, Duration::from_secs(1))?.is_none() {
+///     println!("The service at {} is down", addr);
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[track_caller]
+pub fn connect_optional_timeout(addr: &SocketAddr, timeout: Duration) -> IoOption<TcpStream> {
+    ConnectOptions::new().connect(addr, timeout)
+}
+
+/// Options for [`connect_optional_timeout`].
+#[derive(Clone, Debug)]
+pub struct ConnectOptions {
+    down: KindSet,
+}
+
+impl ConnectOptions {
+    /// Create the default options, where the errors in [`DOWN_KINDS`]
+    /// mean that the service is down.
+    pub fn new() -> Self {
+        ConnectOptions { down: DOWN_KINDS }
+    }
+
+    /// Set the kinds of errors that mean that the service is down.
+    pub fn down_kinds(mut self, kinds: KindSet) -> Self {
+        self.down = kinds;
+        self
+    }
+
+    /// Connect to `addr`, waiting at most `timeout`.
+    #[track_caller]
+    pub fn connect(&self, addr: &SocketAddr, timeout: Duration) -> IoOption<TcpStream> {
+        TcpStream::connect_timeout(addr, timeout).optional_kinds(&self.down)
+    }
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{connect_optional_timeout, ConnectOptions};
+    use crate::KindSet;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    #[test]
+    fn connect() {
+        let timeout = Duration::from_secs(5);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(connect_optional_timeout(&addr, timeout).unwrap().is_some());
+        drop(listener);
+        assert!(connect_optional_timeout(&addr, timeout).unwrap().is_none());
+        let strict = ConnectOptions::new().down_kinds(KindSet::new(&[]));
+        assert!(strict.connect(&addr, timeout).is_err());
+        assert!(connect_optional_timeout(&addr, Duration::ZERO).is_err());
+    }
+}
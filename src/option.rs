@@ -0,0 +1,233 @@
+//! Results of operations on things that may not exist.
+use crate::IoResultOptional;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The result of an operation on something that may not exist.
+///
+/// This is what [`optional`](crate::IoResultOptional::optional)
+/// returns: `Ok(Some(value))` if it exists, `Ok(None)` if it does
+/// not, and `Err(error)` if something else went wrong.
+pub type IoOption<T> = io::Result<Option<T>>;
+
+/// Combinators for an [`IoOption`].
+///
+/// # Examples
+/// ````
+/// use io_result_optional::{IoOption, IoOptionExt, IoResultOptional};
+/// use std::fs;
+/// # use std::io;
+///
+/// fn read_config() -> IoOption<String> {
+///     fs::read_to_string(".app.rc")
+///         .optional()
+///         .or_else_optional(|| fs::read_to_string("/etc/app.rc").optional())
+/// }
+///
+/// # fn main() -> io::Result<()> {
+/// let lines = read_config().map_some(|config| config.lines().count())?;
+/// # Ok(())
+/// # }
+/// ````
+pub trait IoOptionExt<T> {
+    /// Map the value, if it exists.
+    fn map_some<U, F: FnOnce(T) -> U>(self, f: F) -> IoOption<U>;
+
+    /// Do another operation with the value, if it exists.
+    fn and_then_optional<U, F: FnOnce(T) -> IoOption<U>>(self, f: F) -> IoOption<U>;
+
+    /// Do another operation if the value does not exist.
+    fn or_else_optional<F: FnOnce() -> IoOption<T>>(self, f: F) -> IoOption<T>;
+
+    /// Get the value, or the default if it does not exist.
+    fn unwrap_or_default_value(self) -> io::Result<T>
+    where
+        T: Default;
+
+    /// Get the value, or a `NotFound` error if it does not exist.
+    fn required(self) -> io::Result<T>;
+
+    /// Convert to a result where a missing value is an error, that
+    /// can be told apart from other errors.
+    fn missing_or_error(self) -> Result<T, MissingOrError>;
+
+    /// Consider a result that is already optional optional.
+    ///
+    /// This works like [`optional`](crate::IoResultOptional::optional),
+    /// but gives an `IoOption<T>` rather than an `IoOption<Option<T>>`,
+    /// so both a `NotFound` error and `Ok(None)` give `Ok(None)`.
+    ///
+    /// # Examples
+    /// ````
+    /// use io_result_optional::{IoOption, IoOptionExt};
+    /// use std::fs::File;
+    /// use std::io::{self, BufRead, BufReader};
+    ///
+    /// fn first_line(path: &str) -> IoOption<String> {
+    ///     let file = File::open(path)?;
+    ///     BufReader::new(file).lines().next().transpose()
+    /// }
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let line: Option<String> = first_line(".app.rc").optional_flat()?;
+    /// # assert_eq!(line, None);
+    /// # Ok(())
+    /// # }
+    /// ````
+    fn optional_flat(self) -> IoOption<T>;
+}
+
+impl<T> IoOptionExt<T> for IoOption<T> {
+    fn map_some<U, F: FnOnce(T) -> U>(self, f: F) -> IoOption<U> {
+        self.map(|value| value.map(f))
+    }
+
+    fn and_then_optional<U, F: FnOnce(T) -> IoOption<U>>(self, f: F) -> IoOption<U> {
+        match self? {
+            Some(value) => f(value),
+            None => Ok(None),
+        }
+    }
+
+    fn or_else_optional<F: FnOnce() -> IoOption<T>>(self, f: F) -> IoOption<T> {
+        match self? {
+            Some(value) => Ok(Some(value)),
+            None => f(),
+        }
+    }
+
+    fn unwrap_or_default_value(self) -> io::Result<T>
+    where
+        T: Default,
+    {
+        Ok(self?.unwrap_or_default())
+    }
+
+    fn required(self) -> io::Result<T> {
+        // An error is returned as it is, so a NotFound error keeps its
+        // message.
+        self?.ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    #[track_caller]
+    fn optional_flat(self) -> IoOption<T> {
+        self.optional().map(Option::flatten)
+    }
+
+    fn missing_or_error(self) -> Result<T, MissingOrError> {
+        self?.ok_or(MissingOrError::Missing)
+    }
+}
+
+/// An error that is either that something is missing, or another
+/// error.
+#[derive(Debug)]
+pub enum MissingOrError {
+    /// The thing does not exist.
+    Missing,
+    /// Something else went wrong.
+    Error(io::Error),
+}
+
+impl MissingOrError {
+    /// Return true if this is [`MissingOrError::Missing`].
+    pub fn is_missing(&self) -> bool {
+        matches!(self, MissingOrError::Missing)
+    }
+}
+
+impl fmt::Display for MissingOrError {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MissingOrError::Missing => out.write_str("not found"),
+            MissingOrError::Error(e) => e.fmt(out),
+        }
+    }
+}
+
+impl Error for MissingOrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MissingOrError::Missing => None,
+            MissingOrError::Error(e) => Some(e),
+        }
+    }
+}
+
+/// A `NotFound` error is [`MissingOrError::Missing`], and any other
+/// error is [`MissingOrError::Error`].
+impl From<io::Error> for MissingOrError {
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::NotFound {
+            MissingOrError::Missing
+        } else {
+            MissingOrError::Error(error)
+        }
+    }
+}
+
+/// [`MissingOrError::Missing`] is a `NotFound` error.
+impl From<MissingOrError> for io::Error {
+    fn from(error: MissingOrError) -> Self {
+        match error {
+            MissingOrError::Missing => io::ErrorKind::NotFound.into(),
+            MissingOrError::Error(e) => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IoOption, IoOptionExt, MissingOrError};
+    use std::io;
+
+    #[test]
+    fn combinators() {
+        let missing: IoOption<u8> = Ok(None);
+        assert_eq!(
+            missing
+                .or_else_optional(|| Ok(Some(3)))
+                .map_some(|v| v * 2)
+                .unwrap(),
+            Some(6)
+        );
+        let found: IoOption<u8> = Ok(Some(1));
+        assert_eq!(found.and_then_optional(|_| Ok(None::<u8>)).unwrap(), None);
+        let missing: IoOption<u8> = Ok(None);
+        assert_eq!(missing.unwrap_or_default_value().unwrap(), 0);
+        let missing: IoOption<u8> = Ok(None);
+        assert_eq!(
+            missing.required().unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        let failed: IoOption<u8> = Err(io::Error::new(io::ErrorKind::NotFound, "custom"));
+        assert_eq!(failed.required().unwrap_err().to_string(), "custom");
+        let found: IoOption<u8> = Ok(Some(4));
+        assert_eq!(found.required().unwrap(), 4);
+    }
+
+    #[test]
+    fn missing_or_error() {
+        let missing: IoOption<()> = Ok(None);
+        assert!(missing.missing_or_error().unwrap_err().is_missing());
+        let failed: IoOption<()> = Err(io::ErrorKind::TimedOut.into());
+        match failed.missing_or_error() {
+            Err(MissingOrError::Error(e)) => assert_eq!(e.kind(), io::ErrorKind::TimedOut),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(MissingOrError::from(io::Error::from(io::ErrorKind::NotFound)).is_missing());
+    }
+
+    #[test]
+    fn optional_flat() {
+        let missing: IoOption<u8> = Err(io::ErrorKind::NotFound.into());
+        assert_eq!(missing.optional_flat().unwrap(), None);
+        let empty: IoOption<u8> = Ok(None);
+        assert_eq!(empty.optional_flat().unwrap(), None);
+        let found: IoOption<u8> = Ok(Some(2));
+        assert_eq!(found.optional_flat().unwrap(), Some(2));
+        let failed: IoOption<u8> = Err(io::ErrorKind::TimedOut.into());
+        assert!(failed.optional_flat().is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Records of attempts to load things that may not exist.
+//!
+//! With the `serde` feature, a [`LoadOutcome`] can be serialized, to
+//! keep a machine-readable record of which files were consulted.
+//! With the `telemetry` feature, the helpers in [`fs`](crate::fs)
+//! give a record of each attempt to the telemetry hook, see
+//! `telemetry::Attempt::to_load_outcome`.
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// A record of an attempt to load something that may not exist.
+///
+/// The loaded value, if any, is kept in the [`outcome`](Self::outcome).
+/// For a record of just the attempt, `T` is `()`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LoadOutcome<T = ()> {
+    /// The path that was loaded.
+    pub path: PathBuf,
+    /// The name of the function making the attempt, such as
+    /// `"load_or_embedded"`.
+    pub operation: &'static str,
+    /// How the attempt ended.
+    pub outcome: Outcome<T>,
+    /// When the attempt started.
+    pub timestamp: SystemTime,
+    /// How long the attempt took.
+    pub duration: Duration,
+}
+
+/// How an attempt to load something ended.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Outcome<T = ()> {
+    /// The thing was found, with this value.
+    Found(T),
+    /// The thing did not exist.
+    Missing,
+    /// There was an error.
+    ///
+    /// This is serialized as the kind and message of the error.
+    Error(#[cfg_attr(feature = "serde", serde(serialize_with = "serialize_error"))] io::Error),
+}
+
+impl Outcome {
+    /// Get the outcome of `result`, without the value.
+    ///
+    /// The error of the outcome has the kind and message of the error
+    /// of `result`.
+    pub fn of<T>(result: &io::Result<Option<T>>) -> Self {
+        Outcome::of_found(result, Option::is_some)
+    }
+
+    /// Get the outcome of `result`, where `found` tells if a value
+    /// means that the thing was found.
+    pub(crate) fn of_found<T>(result: &io::Result<T>, found: impl FnOnce(&T) -> bool) -> Self {
+        match result {
+            Ok(value) if found(value) => Outcome::Found(()),
+            Ok(_) => Outcome::Missing,
+            Err(e) => Outcome::Error(copy_error(e)),
+        }
+    }
+}
+
+impl<T> Outcome<T> {
+    /// Return true if the thing was found.
+    pub fn is_found(&self) -> bool {
+        matches!(self, Outcome::Found(_))
+    }
+
+    /// Return true if the thing did not exist.
+    pub fn is_missing(&self) -> bool {
+        matches!(self, Outcome::Missing)
+    }
+
+    /// Return true if there was an error.
+    pub fn is_error(&self) -> bool {
+        matches!(self, Outcome::Error(_))
+    }
+
+    /// Convert this outcome back to a result.
+    pub fn into_result(self) -> io::Result<Option<T>> {
+        match self {
+            Outcome::Found(value) => Ok(Some(value)),
+            Outcome::Missing => Ok(None),
+            Outcome::Error(e) => Err(e),
+        }
+    }
+}
+
+impl<T> From<io::Result<Option<T>>> for Outcome<T> {
+    fn from(result: io::Result<Option<T>>) -> Self {
+        match result {
+            Ok(Some(value)) => Outcome::Found(value),
+            Ok(None) => Outcome::Missing,
+            Err(e) => Outcome::Error(e),
+        }
+    }
+}
+
+/// The error of a cloned outcome has the kind and message of the
+/// original error, but no source.
+impl<T: Clone> Clone for Outcome<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Outcome::Found(value) => Outcome::Found(value.clone()),
+            Outcome::Missing => Outcome::Missing,
+            Outcome::Error(e) => Outcome::Error(copy_error(e)),
+        }
+    }
+}
+
+fn copy_error(error: &io::Error) -> io::Error {
+    io::Error::new(error.kind(), error.to_string())
+}
+
+/// Serialize an error as its kind, such as `"PermissionDenied"`, and
+/// its message.
+#[cfg(feature = "serde")]
+fn serialize_error<S: serde::Serializer>(error: &io::Error, s: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+    let mut record = s.serialize_struct("Error", 2)?;
+    record.serialize_field("kind", &format_args!("{:?}", error.kind()))?;
+    record.serialize_field("message", &format_args!("{}", error))?;
+    record.end()
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::{LoadOutcome, Outcome};
+    use std::io;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn serialize() {
+        let outcome = LoadOutcome {
+            path: "app.rc".into(),
+            operation: "load_or_embedded",
+            outcome: Outcome::<()>::Error(io::Error::new(io::ErrorKind::PermissionDenied, "nope")),
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(17),
+            duration: Duration::from_millis(3),
+        };
+        assert_eq!(
+            serde_json::to_string(&outcome).unwrap(),
+            "{\"path\":\"app.rc\",\"operation\":\"load_or_embedded\",\
+             \"outcome\":{\"error\":{\"kind\":\"PermissionDenied\",\"message\":\"nope\"}},\
+             \"timestamp\":{\"secs_since_epoch\":17,\"nanos_since_epoch\":0},\
+             \"duration\":{\"secs\":0,\"nanos\":3000000}}"
+        );
+        let missing = serde_json::to_string(&Outcome::<()>::Missing).unwrap();
+        assert_eq!(missing, "\"missing\"");
+        let found = serde_json::to_string(&Outcome::Found(17)).unwrap();
+        assert_eq!(found, "{\"found\":17}");
+    }
+}
@@ -0,0 +1,152 @@
+//! Explaining why permission was denied.
+//!
+//! This module is available on unix with the `unix-permissions`
+//! feature.
+//!
+//! A `PermissionDenied` error seldom says why permission was denied.
+//! The helpers here look at the owner, group and mode of the path and
+//! its parent, and add them to the message of the error, along with
+//! the user and group of the process.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::permissions::ExplainPermissions;
+//! use io_result_optional::IoResultOptional;
+//! use std::fs;
+//! # use std::io;
+//!
+//! # fn main() -> io::Result<()> {
+//! let path = "/var/lib/app/state";
+//! let state = fs::read(path).explain_permissions(path).optional()?;
+//! # Ok(())
+//! # }
+//! ````
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Add an explanation to a `PermissionDenied` error about `path`.
+///
+/// The kind of the error is kept, and the original error is its
+/// source.
+/// Any other error is returned as it is.
+pub fn explain_permission_denied<P: AsRef<Path>>(error: io::Error, path: P) -> io::Error {
+    if error.kind() != io::ErrorKind::PermissionDenied {
+        return error;
+    }
+    let path = path.as_ref();
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let explained = Explained {
+        path: path.into(),
+        file: Access::of(path),
+        parent: (parent.into(), Access::of(parent)),
+        // SAFETY: These calls have no preconditions and can not fail.
+        process: unsafe { (libc::geteuid(), libc::getegid()) },
+        source: error,
+    };
+    io::Error::new(io::ErrorKind::PermissionDenied, explained)
+}
+
+/// Explaining `PermissionDenied` errors in results.
+pub trait ExplainPermissions<T> {
+    /// Add an explanation to a `PermissionDenied` error about `path`.
+    ///
+    /// See [`explain_permission_denied`].
+    fn explain_permissions<P: AsRef<Path>>(self, path: P) -> io::Result<T>;
+}
+
+impl<T> ExplainPermissions<T> for io::Result<T> {
+    fn explain_permissions<P: AsRef<Path>>(self, path: P) -> io::Result<T> {
+        self.map_err(|e| explain_permission_denied(e, path))
+    }
+}
+
+/// The owner, group and mode of a path, if it could be found.
+#[derive(Debug)]
+struct Access(Option<(u32, u32, u32)>);
+
+impl Access {
+    fn of(path: &Path) -> Self {
+        Access(
+            fs::metadata(path)
+                .ok()
+                .map(|meta| (meta.uid(), meta.gid(), meta.mode() & 0o7777)),
+        )
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some((uid, gid, mode)) => {
+                write!(out, "owner {}, group {}, mode {:04o}", uid, gid, mode)
+            }
+            None => out.write_str("not accessible"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Explained {
+    path: PathBuf,
+    file: Access,
+    parent: (PathBuf, Access),
+    process: (u32, u32),
+    source: io::Error,
+}
+
+impl fmt::Display for Explained {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            out,
+            "{}: {} ({}; parent {}: {}; process user {}, group {})",
+            self.path.display(),
+            self.source,
+            self.file,
+            self.parent.0.display(),
+            self.parent.1,
+            self.process.0,
+            self.process.1,
+        )
+    }
+}
+
+impl Error for Explained {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::explain_permission_denied;
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn explain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        let error = io::Error::from(io::ErrorKind::PermissionDenied);
+        let error = explain_permission_denied(error, &path);
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        let message = error.to_string();
+        assert!(message.starts_with(&format!("{}: ", path.display())));
+        assert!(message.contains(", mode 0640; parent "));
+
+        let other = explain_permission_denied(io::ErrorKind::NotFound.into(), &path);
+        assert_eq!(
+            other.to_string(),
+            io::Error::from(io::ErrorKind::NotFound).to_string()
+        );
+    }
+}
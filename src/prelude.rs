@@ -0,0 +1,21 @@
+//! The traits and aliases that are commonly used with this crate.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::prelude::*;
+//! use std::fs;
+//!
+//! fn config(path: &str) -> Result<Option<String>> {
+//!     Ok(fs::read_to_string(path).optional().with_path(path)?)
+//! }
+//! # assert!(config("nosuch.toml").unwrap().is_none());
+//! ````
+pub use crate::kinds::MapKind;
+pub use crate::option::{IoOption, IoOptionExt};
+pub use crate::{IoResultOptional, OptionalIoError, ResultOptional, WithPath};
+
+#[cfg(all(unix, feature = "unix-permissions"))]
+pub use crate::permissions::ExplainPermissions;
+
+/// A result with an [`OptionalIoError`] by default.
+pub type Result<T, E = OptionalIoError> = std::result::Result<T, E>;
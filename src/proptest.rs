@@ -0,0 +1,92 @@
+//! Strategies for property testing code that handles io errors.
+//!
+//! The strategies generate errors with a realistic distribution:
+//! mostly things that are missing, often transient errors, and
+//! sometimes any other kind, created either with a kind or from a raw
+//! os error code of the platform.
+//!
+//! This module is available with the `proptest` feature.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::proptest::io_result;
+//! use io_result_optional::IoResultOptional;
+//! use proptest::prelude::*;
+//! use proptest::test_runner::TestRunner;
+//! use std::io;
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&io_result(any::<u8>()), |result| {
+//!         let missing = matches!(&result, Err(e) if e.kind() == io::ErrorKind::NotFound);
+//!         prop_assert_eq!(matches!(result.optional(), Ok(None)), missing);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ````
+use crate::kinds::{ALL_KINDS, MISSING_KINDS, TRANSIENT_KINDS};
+use crate::test_util::OS_ERRORS;
+use ::proptest::prelude::*;
+use ::proptest::sample::select;
+use std::io::{self, ErrorKind};
+
+/// A strategy for error kinds, weighted towards missing and transient
+/// kinds.
+pub fn error_kind() -> impl Strategy<Value = ErrorKind> {
+    let all = ALL_KINDS
+        .iter()
+        .chain(Some(ErrorKind::Other))
+        .collect::<Vec<_>>();
+    prop_oneof![
+        4 => select(MISSING_KINDS.iter().collect::<Vec<_>>()),
+        2 => select(TRANSIENT_KINDS.iter().collect::<Vec<_>>()),
+        1 => select(all),
+    ]
+}
+
+/// A strategy for io errors.
+///
+/// Most errors are created from a kind given by [`error_kind`], and
+/// some from a raw os error code of the platform.
+pub fn io_error() -> impl Strategy<Value = io::Error> {
+    let from_kind = error_kind().prop_map(|kind| io::Error::new(kind, "generated error"));
+    if OS_ERRORS.is_empty() {
+        from_kind.boxed()
+    } else {
+        let codes = OS_ERRORS.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+        prop_oneof![
+            3 => from_kind,
+            1 => select(codes).prop_map(io::Error::from_raw_os_error),
+        ]
+        .boxed()
+    }
+}
+
+/// A strategy for io results, with values from `value` about half of
+/// the time, and errors from [`io_error`] otherwise.
+pub fn io_result<T: std::fmt::Debug>(
+    value: impl Strategy<Value = T>,
+) -> impl Strategy<Value = io::Result<T>> {
+    prop_oneof![value.prop_map(Ok), io_error().prop_map(Err),]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::io_result;
+    use crate::kinds::MISSING_KINDS;
+    use crate::IoResultOptional;
+    use ::proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn optional_kinds(result in io_result(any::<u8>())) {
+            let missing = matches!(&result, Err(e) if MISSING_KINDS.matches(e));
+            let value = result.as_ref().ok().copied();
+            match result.optional_kinds(&MISSING_KINDS) {
+                Ok(Some(v)) => prop_assert_eq!(Some(v), value),
+                Ok(None) => prop_assert!(missing),
+                Err(_) => prop_assert!(!missing && value.is_none()),
+            }
+        }
+    }
+}
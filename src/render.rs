@@ -0,0 +1,167 @@
+//! Human-readable reports of io errors.
+//!
+//! A [`Report`] shows an error on several lines, with its kind, os
+//! error code, the errors that caused it, and a hint about what may
+//! be wrong for some common cases.
+//! It is meant for command line tools to show when they fail.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::render::Report;
+//! use std::fs;
+//!
+//! let path = "/nosuch/dir/config.toml";
+//! if let Err(e) = fs::read(path) {
+//!     eprint!("{}", Report::new(&e).path(path).operation("reading configuration"));
+//! }
+//! ````
+//! This prints something like the following:
+//! ````text
+//! Error reading configuration: /nosuch/dir/config.toml
+//!   No such file or directory (os error 2)
+//!   kind: NotFound, os error code 2
+//!   hint: the directory /nosuch/dir does not exist
+//! ````
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// A report of an io error, shown on several lines.
+///
+/// Each line ends with a newline.
+#[derive(Debug)]
+pub struct Report<'a> {
+    error: &'a io::Error,
+    path: Option<&'a Path>,
+    operation: Option<&'a str>,
+}
+
+impl<'a> Report<'a> {
+    /// Create a report of `error`.
+    pub fn new(error: &'a io::Error) -> Self {
+        Report {
+            error,
+            path: None,
+            operation: None,
+        }
+    }
+
+    /// Set the path that the error is about.
+    ///
+    /// The path is shown, and used to give better hints.
+    pub fn path<P: AsRef<Path> + ?Sized>(mut self, path: &'a P) -> Self {
+        self.path = Some(path.as_ref());
+        self
+    }
+
+    /// Set what was being done, such as `"reading configuration"`.
+    pub fn operation(mut self, operation: &'a str) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Get a hint about what may be wrong, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        let parent = self
+            .path
+            .and_then(Path::parent)
+            .filter(|parent| !parent.as_os_str().is_empty());
+        match self.error.kind() {
+            ErrorKind::NotFound => parent
+                .filter(|parent| !parent.exists())
+                .map(|parent| format!("the directory {} does not exist", parent.display())),
+            ErrorKind::PermissionDenied => Some(match (self.path, parent) {
+                (Some(path), Some(parent)) => format!(
+                    "check the permissions of {} and of the directory {}",
+                    path.display(),
+                    parent.display(),
+                ),
+                (Some(path), None) => format!("check the permissions of {}", path.display()),
+                _ => "check the permissions of the file and its directory".into(),
+            }),
+            ErrorKind::ReadOnlyFilesystem => Some("the file system is mounted read-only".into()),
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded => {
+                Some("there is no space left, or a disk quota is exceeded".into())
+            }
+            ErrorKind::NotADirectory => {
+                Some("a part of the path is a file, not a directory".into())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        match (self.operation, self.path) {
+            (Some(op), Some(path)) => writeln!(out, "Error {}: {}", op, path.display())?,
+            (Some(op), None) => writeln!(out, "Error {}", op)?,
+            (None, Some(path)) => writeln!(out, "Error: {}", path.display())?,
+            (None, None) => writeln!(out, "Error")?,
+        }
+        writeln!(out, "  {}", self.error)?;
+        write!(out, "  kind: {:?}", self.error.kind())?;
+        match self.error.raw_os_error() {
+            Some(code) => writeln!(out, ", os error code {}", code)?,
+            None => writeln!(out)?,
+        }
+        let mut source = self.error.source();
+        while let Some(cause) = source {
+            writeln!(out, "  caused by: {}", cause)?;
+            source = cause.source();
+        }
+        if let Some(hint) = self.hint() {
+            writeln!(out, "  hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Report;
+    use std::io;
+
+    #[test]
+    fn missing_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nosuch").join("file");
+        let error = io::Error::new(io::ErrorKind::NotFound, "gone");
+        let report = Report::new(&error).path(&path).operation("reading");
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "Error reading: {}\n  gone\n  kind: NotFound\n  hint: the directory {} does not exist\n",
+                path.display(),
+                path.parent().unwrap().display(),
+            )
+        );
+    }
+
+    #[test]
+    fn cause_chain() {
+        let cause = io::Error::new(io::ErrorKind::TimedOut, "too slow");
+        let error = io::Error::other(Wrapper(cause));
+        let report = Report::new(&error).to_string();
+        assert_eq!(
+            report,
+            "Error\n  wrapped\n  kind: Other\n  caused by: too slow\n"
+        );
+    }
+
+    #[derive(Debug)]
+    struct Wrapper(io::Error);
+
+    impl std::fmt::Display for Wrapper {
+        fn fmt(&self, out: &mut std::fmt::Formatter) -> std::fmt::Result {
+            out.write_str("wrapped")
+        }
+    }
+
+    impl std::error::Error for Wrapper {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+}
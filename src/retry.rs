@@ -0,0 +1,217 @@
+//! Retrying operations that fail for a while.
+//!
+//! A [`RetryPolicy`] tells which errors to retry, how many times, and
+//! how long to wait between attempts.
+//! It is also used for each file opened by
+//! [`open_many_optional`](crate::fs::open_many_optional).
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::retry::RetryPolicy;
+//! use io_result_optional::IoResultOptional;
+//! use std::fs::File;
+//! # use std::io;
+//!
+//! # fn main() -> io::Result<()> {
+//! let policy = RetryPolicy::fd_exhausted();
+//! let file = policy.run(|| File::open("data/0001.json")).optional()?;
+//! # Ok(())
+//! # }
+//! ````
+use crate::kinds::{is_fd_exhausted, KindSet, TRANSIENT_KINDS};
+use std::io;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Which errors to retry, and how.
+///
+/// The delay before the first retry is the initial delay, and each
+/// following delay is twice the one before, up to the max delay.
+///
+/// With the `serde` feature, a policy can be serialized, with the
+/// delays in milliseconds, such as
+/// `{"retries": 3, "initial_delay_ms": 10, "max_delay_ms": 1000, "kinds": ["transient"]}`.
+/// All but `retries` may be left out when deserializing, to get the
+/// defaults of [`RetryPolicy::new`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryPolicy {
+    retries: u32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "initial_delay_ms",
+            with = "millis",
+            default = "default_initial"
+        )
+    )]
+    initial_delay: Duration,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "max_delay_ms", with = "millis", default = "default_max")
+    )]
+    max_delay: Duration,
+    #[cfg_attr(feature = "serde", serde(default))]
+    kinds: KindSet,
+    #[cfg_attr(feature = "serde", serde(default))]
+    fd_exhausted: bool,
+}
+
+fn default_initial() -> Duration {
+    Duration::from_millis(10)
+}
+
+fn default_max() -> Duration {
+    Duration::from_secs(1)
+}
+
+/// Durations as a number of milliseconds.
+#[cfg(feature = "serde")]
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::convert::TryFrom;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(value: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u64(u64::try_from(value.as_millis()).unwrap_or(u64::MAX))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        u64::deserialize(d).map(Duration::from_millis)
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy retrying nothing, with delays from 10 ms to 1 s.
+    ///
+    /// Use [`retry_kinds`](Self::retry_kinds) or
+    /// [`retry_fd_exhausted`](Self::retry_fd_exhausted) to tell what to
+    /// retry.
+    pub fn new(retries: u32) -> Self {
+        RetryPolicy {
+            retries,
+            initial_delay: default_initial(),
+            max_delay: default_max(),
+            kinds: KindSet::default(),
+            fd_exhausted: false,
+        }
+    }
+
+    /// A policy retrying 5 times when out of file descriptors.
+    ///
+    /// See [`is_fd_exhausted`].
+    pub fn fd_exhausted() -> Self {
+        RetryPolicy::new(5).retry_fd_exhausted(true)
+    }
+
+    /// A policy retrying 3 times on [`TRANSIENT_KINDS`].
+    pub fn transient() -> Self {
+        RetryPolicy::new(3).retry_kinds(TRANSIENT_KINDS)
+    }
+
+    /// Set the delay before the first retry, and the longest delay.
+    pub fn backoff(mut self, initial_delay: Duration, max_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the kinds of errors to retry.
+    pub fn retry_kinds(mut self, kinds: KindSet) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Set if errors that the process or system is out of file
+    /// descriptors should be retried.
+    pub fn retry_fd_exhausted(mut self, value: bool) -> Self {
+        self.fd_exhausted = value;
+        self
+    }
+
+    /// Return true if `error` should be retried.
+    pub fn should_retry(&self, error: &io::Error) -> bool {
+        self.kinds.matches(error) || (self.fd_exhausted && is_fd_exhausted(error))
+    }
+
+    /// Get the delay before retry number `retry`, counting from 0.
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.initial_delay
+            .checked_mul(1 << retry.min(31))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Run `operation`, and retry it as long as this policy says so.
+    ///
+    /// The last error is returned if all retries fail.
+    pub fn run<T, F: FnMut() -> io::Result<T>>(&self, mut operation: F) -> io::Result<T> {
+        let mut retry = 0;
+        loop {
+            match operation() {
+                Err(ref e) if retry < self.retries && self.should_retry(e) => {
+                    sleep(self.delay(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn delays() {
+        let policy =
+            RetryPolicy::new(3).backoff(Duration::from_millis(1), Duration::from_millis(5));
+        let delays = (0..4)
+            .map(|r| policy.delay(r).as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [1, 2, 4, 5]);
+        assert_eq!(policy.delay(100), Duration::from_millis(5));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let policy: RetryPolicy =
+            serde_json::from_str(r#"{"retries": 3, "kinds": "transient", "max_delay_ms": 500}"#)
+                .unwrap();
+        let expected =
+            RetryPolicy::transient().backoff(Duration::from_millis(10), Duration::from_millis(500));
+        assert_eq!(policy, expected);
+        let json = serde_json::to_string(&RetryPolicy::fd_exhausted()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"retries":5,"initial_delay_ms":10,"max_delay_ms":1000,"kinds":[],"fd_exhausted":true}"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn retry_fd_exhausted() {
+        let policy = RetryPolicy::fd_exhausted().backoff(Duration::ZERO, Duration::ZERO);
+        let mut attempts = 0;
+        let result = policy.run(|| {
+            attempts += 1;
+            if attempts < 3 {
+                Err(io::Error::from_raw_os_error(libc::EMFILE))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut attempts = 0;
+        let result = policy.run(|| -> io::Result<()> {
+            attempts += 1;
+            Err(io::ErrorKind::NotFound.into())
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
@@ -0,0 +1,215 @@
+//! Read and write loops that handle interruptions and short transfers.
+//!
+//! A single call to [`Read::read`] or [`Write::write`] may transfer
+//! fewer bytes than asked for, or fail with `Interrupted` when nothing
+//! is really wrong.
+//! These helpers loop until done, and return the total number of
+//! bytes transferred.
+//! For non-blocking input, [`read_nonblocking_optional`] reads what is
+//! available now.
+use crate::IoOption;
+use std::io::{self, Read, Write};
+
+/// Read into `buf` until it is full or the end of input is reached.
+///
+/// Return the number of bytes read, which is less than the length of
+/// `buf` only if the end of input was reached.
+/// Reads that fail with `Interrupted` are retried.
+///
+/// Unlike [`Read::read_exact`], reaching the end of input early is not
+/// an error.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::rw::read_full;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let mut header = [0; 8];
+/// let len = read_full(&mut &b"short"[..], &mut header)?;
+/// assert_eq!(&header[..len], b"short");
+/// # Ok(())
+/// # }
+/// ````
+pub fn read_full<R: Read + ?Sized>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(len) => total += len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+/// Write all of `buf`.
+///
+/// Return the number of bytes written, which is always the length of
+/// `buf`.
+/// Writes that fail with `Interrupted` are retried, and a write that
+/// writes nothing gives a `WriteZero` error.
+///
+/// This is like [`Write::write_all`], but returns the number of bytes
+/// written.
+pub fn write_full<W: Write + ?Sized>(writer: &mut W, buf: &[u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match writer.write(&buf[total..]) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    format!("wrote only {} of {} bytes", total, buf.len()),
+                ))
+            }
+            Ok(len) => total += len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+/// Read what is available now from a non-blocking reader, such as a
+/// pipe or socket.
+///
+/// Make one read into `buf`, and return `Ok(Some(len))` with the
+/// number of bytes read, which is zero at the end of input.
+/// If nothing can be read without blocking, the result is `Ok(None)`.
+/// A read that fails with `Interrupted` is retried, and any other
+/// error is returned.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::rw::read_nonblocking_optional;
+/// use std::net::{TcpListener, TcpStream};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// # let listener = TcpListener::bind("127.0.0.1:0")?;
+/// # let _client = TcpStream::connect(listener.local_addr()?)?;
+/// let (mut stream, _) = listener.accept()?;
+/// stream.set_nonblocking(true)?;
+/// let mut buf = [0; 1024];
+/// match read_nonblocking_optional(&mut stream, &mut buf)? {
+///     Some(0) => println!("The peer is done"),
+///     Some(len) => println!("Got {} bytes", len),
+///     None => println!("Nothing yet"),
+/// }
+/// # Ok(())
+/// # }
+/// ````
+pub fn read_nonblocking_optional<R: Read + ?Sized>(
+    reader: &mut R,
+    buf: &mut [u8],
+) -> IoOption<usize> {
+    loop {
+        match reader.read(buf) {
+            Ok(len) => return Ok(Some(len)),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_full, read_nonblocking_optional, write_full};
+    use std::io::{self, Read, Write};
+
+    /// Transfers at most two bytes at a time, and is interrupted
+    /// every other time.
+    struct Choppy {
+        data: Vec<u8>,
+        pos: usize,
+        interrupt: bool,
+    }
+
+    impl Choppy {
+        fn chop(&mut self) -> io::Result<()> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                Err(io::ErrorKind::Interrupted.into())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Read for Choppy {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.chop()?;
+            let len = buf.len().min(2).min(self.data.len() - self.pos);
+            buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        }
+    }
+
+    impl Write for Choppy {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.chop()?;
+            let len = buf.len().min(2);
+            self.data.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Never has anything to read now.
+    struct Blocked;
+
+    impl Read for Blocked {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+
+    #[test]
+    fn choppy() {
+        let mut io = Choppy {
+            data: Vec::new(),
+            pos: 0,
+            interrupt: false,
+        };
+        assert_eq!(write_full(&mut io, b"hello").unwrap(), 5);
+        let mut buf = [0; 8];
+        assert_eq!(read_full(&mut io, &mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+    }
+
+    #[test]
+    fn nonblocking() {
+        let mut io = Choppy {
+            data: b"hi".to_vec(),
+            pos: 0,
+            interrupt: false,
+        };
+        let mut buf = [0; 8];
+        assert_eq!(
+            read_nonblocking_optional(&mut io, &mut buf).unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            read_nonblocking_optional(&mut io, &mut buf).unwrap(),
+            Some(0)
+        );
+        let mut blocked = io::Cursor::new(b"").chain(Blocked);
+        assert_eq!(
+            read_nonblocking_optional(&mut blocked, &mut buf).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn write_zero() {
+        let mut full = [0; 2];
+        let err = write_full(&mut &mut full[..], b"hello").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    }
+}
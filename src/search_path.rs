@@ -0,0 +1,278 @@
+//! Looking up files in an ordered list of directories.
+use crate::kinds::MISSING_KINDS;
+use crate::probe;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io;
+use std::iter::FromIterator;
+use std::path::{Path, PathBuf};
+
+/// An ordered list of directories to look for files in.
+///
+/// A file is looked up in each directory in order, and a directory
+/// that does not exist is just like a directory that does not
+/// contain the file.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::SearchPath;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let mut search = SearchPath::new();
+/// search.push("./plugins");
+/// search.push("/usr/lib/app/plugins");
+/// if let Some(plugin) = search.find("formatter.so")? {
+///     // Load the plugin ...
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchPath {
+    dirs: Vec<PathBuf>,
+}
+
+impl SearchPath {
+    /// Create an empty search path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a search path from a `PATH`-style environment variable.
+    ///
+    /// The value is split on the platform separator (`:` on unix, `;`
+    /// on windows).
+    /// On unix, an empty entry means the current directory, as in
+    /// `PATH`, while empty entries are ignored on other platforms.
+    ///
+    /// If the variable is not set, an error of kind `NotFound` is
+    /// returned, so `.optional()` can be used to tell that case apart.
+    ///
+    /// # Examples
+    /// ````
+    /// use io_result_optional::SearchPath;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let path = SearchPath::from_env("PATH")?;
+    /// if let Some(git) = path.find("git")? {
+    ///     println!("Found git at {}", git.display());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn from_env<K: AsRef<OsStr>>(key: K) -> io::Result<Self> {
+        Self::from_env_with(key.as_ref(), |key| env::var_os(key))
+    }
+
+    /// Like [`from_env`](Self::from_env), getting variables with
+    /// `lookup`.
+    fn from_env_with(key: &OsStr, lookup: impl Fn(&OsStr) -> Option<OsString>) -> io::Result<Self> {
+        let value = env_value(key, lookup)?;
+        Ok(env::split_paths(&value)
+            .filter_map(|dir| {
+                if dir.as_os_str().is_empty() {
+                    if cfg!(unix) {
+                        Some(PathBuf::from("."))
+                    } else {
+                        None
+                    }
+                } else {
+                    Some(dir)
+                }
+            })
+            .collect())
+    }
+
+    /// Create a search path from an XDG `*_DIRS` environment variable,
+    /// following the [XDG Base Directory Specification].
+    ///
+    /// Entries that are empty or relative are ignored.
+    /// If `XDG_DATA_DIRS` or `XDG_CONFIG_DIRS` is unset or empty, the
+    /// default value from the specification is used.
+    /// Any other variable that is not set gives an error of kind
+    /// `NotFound`.
+    ///
+    /// Note that the corresponding `*_HOME` directory, which should be
+    /// searched first, is not included.
+    ///
+    /// [XDG Base Directory Specification]: https://specifications.freedesktop.org/basedir-spec/latest/
+    ///
+    /// # Examples
+    /// ````
+    /// use io_result_optional::SearchPath;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let search = SearchPath::from_xdg_env("XDG_DATA_DIRS")?;
+    /// if let Some(plugin) = search.find("app/plugin.so")? {
+    ///     // Load the plugin ...
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn from_xdg_env<K: AsRef<OsStr>>(key: K) -> io::Result<Self> {
+        Self::from_xdg_env_with(key.as_ref(), |key| env::var_os(key))
+    }
+
+    /// Like [`from_xdg_env`](Self::from_xdg_env), getting variables
+    /// with `lookup`.
+    fn from_xdg_env_with(
+        key: &OsStr,
+        lookup: impl Fn(&OsStr) -> Option<OsString>,
+    ) -> io::Result<Self> {
+        let default = match key.to_str() {
+            Some("XDG_DATA_DIRS") => Some("/usr/local/share/:/usr/share/"),
+            Some("XDG_CONFIG_DIRS") => Some("/etc/xdg"),
+            _ => None,
+        };
+        let value = match (env_value(key, lookup), default) {
+            (Ok(value), Some(default)) if value.is_empty() => default.into(),
+            (Ok(value), _) => value,
+            (Err(_), Some(default)) => default.into(),
+            (Err(e), None) => return Err(e),
+        };
+        Ok(env::split_paths(&value)
+            .filter(|dir| dir.is_absolute())
+            .collect())
+    }
+
+    /// Add a directory last in this search path.
+    pub fn push<P: Into<PathBuf>>(&mut self, dir: P) {
+        self.dirs.push(dir.into());
+    }
+
+    /// The directories of this search path, in order.
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Find the first existing `name` in the directories.
+    ///
+    /// Return `Ok(None)` if `name` does not exist in any of them.
+    /// Any other error checking a directory is returned.
+    pub fn find<P: AsRef<Path>>(&self, name: P) -> io::Result<Option<PathBuf>> {
+        self.candidates(name.as_ref()).next().transpose()
+    }
+
+    /// Find all existing `name` in the directories, in order.
+    pub fn find_all<P: AsRef<Path>>(&self, name: P) -> io::Result<Vec<PathBuf>> {
+        self.candidates(name.as_ref()).collect()
+    }
+
+    /// Open the first existing `name` in the directories.
+    ///
+    /// Return the path and the opened file, or `Ok(None)` if `name`
+    /// does not exist in any of the directories.
+    pub fn open<P: AsRef<Path>>(&self, name: P) -> io::Result<Option<(PathBuf, File)>> {
+        let name = name.as_ref();
+        for dir in &self.dirs {
+            let path = dir.join(name);
+            if let Some(file) = probe(File::open(&path), &MISSING_KINDS)? {
+                return Ok(Some((path, file)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn candidates<'a>(&'a self, name: &'a Path) -> impl Iterator<Item = io::Result<PathBuf>> + 'a {
+        self.dirs.iter().filter_map(move |dir| {
+            let path = dir.join(name);
+            match probe(fs::metadata(&path), &MISSING_KINDS) {
+                Ok(Some(_)) => Some(Ok(path)),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+}
+
+fn env_value(key: &OsStr, lookup: impl Fn(&OsStr) -> Option<OsString>) -> io::Result<OsString> {
+    lookup(key).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("environment variable {} is not set", key.to_string_lossy()),
+        )
+    })
+}
+
+impl<P: Into<PathBuf>> FromIterator<P> for SearchPath {
+    fn from_iter<I: IntoIterator<Item = P>>(iter: I) -> Self {
+        SearchPath {
+            dirs: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<P: Into<PathBuf>> Extend<P> for SearchPath {
+    fn extend<I: IntoIterator<Item = P>>(&mut self, iter: I) {
+        self.dirs.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchPath;
+    use crate::IoResultOptional;
+    use std::ffi::{OsStr, OsString};
+    use std::fs;
+    use std::io::Read;
+    use std::iter::FromIterator;
+    use std::path::Path;
+
+    /// An environment with only `key` set to `value`.
+    fn only(key: &'static str, value: &'static str) -> impl Fn(&OsStr) -> Option<OsString> {
+        move |k| (k == key).then(|| value.into())
+    }
+
+    #[test]
+    fn find_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b, c) = (
+            dir.path().join("a"),
+            dir.path().join("b"),
+            dir.path().join("c"),
+        );
+        fs::create_dir(&b).unwrap();
+        fs::create_dir(&c).unwrap();
+        fs::write(b.join("x"), "in b").unwrap();
+        fs::write(c.join("x"), "in c").unwrap();
+        let search = SearchPath::from_iter([&a, &b, &c]);
+        assert_eq!(search.find("x").unwrap(), Some(b.join("x")));
+        assert_eq!(search.find_all("x").unwrap(), [b.join("x"), c.join("x")]);
+        assert_eq!(search.find("y").unwrap(), None);
+        let (path, mut file) = search.open("x").unwrap().unwrap();
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        assert_eq!((path, content.as_str()), (b.join("x"), "in b"));
+        assert!(search.open("y").unwrap().is_none());
+    }
+
+    #[test]
+    fn from_env() {
+        let env = only("PATH", "/a::b");
+        let search = SearchPath::from_env_with("PATH".as_ref(), &env).unwrap();
+        if cfg!(unix) {
+            assert_eq!(
+                search.dirs(),
+                [Path::new("/a"), Path::new("."), Path::new("b")]
+            );
+        }
+        let result = SearchPath::from_env_with("MANPATH".as_ref(), &env);
+        assert!(result.optional().unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_xdg_env() {
+        let env = only("XDG_DATA_DIRS", "/a::b:/c/");
+        let search = SearchPath::from_xdg_env_with("XDG_DATA_DIRS".as_ref(), &env).unwrap();
+        assert_eq!(search.dirs(), [Path::new("/a"), Path::new("/c")]);
+        let search = SearchPath::from_xdg_env_with("XDG_CONFIG_DIRS".as_ref(), &env).unwrap();
+        assert_eq!(search.dirs(), [Path::new("/etc/xdg")]);
+        let result = SearchPath::from_xdg_env_with("XDG_OTHER_DIRS".as_ref(), &env);
+        assert!(result.optional().unwrap().is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! Strict mode, where nothing is considered optional.
+//!
+//! In strict mode, every `optional()` conversion (and its variants)
+//! returns the original error instead of `Ok(None)`.
+//! This is useful in integration tests, to make sure that files that
+//! should exist do, and in deployments where a missing file should
+//! never go unnoticed.
+//!
+//! Strict mode is off by default, unless the environment variable
+//! `IO_RESULT_OPTIONAL_STRICT` is set to `1` or `true` when it is
+//! first checked.
+//! It can be set for the whole process with [`set_strict`] or
+//! [`strict_scope`], and overridden for the current thread with
+//! [`set_thread_strict`] or [`thread_strict_scope`].
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::strict::thread_strict_scope;
+//! use io_result_optional::IoResultOptional;
+//! use std::fs::File;
+//!
+//! let _strict = thread_strict_scope(true);
+//! assert!(File::open("nosuch.file").optional().is_err());
+//! ````
+use std::cell::Cell;
+use std::env;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const UNSET: u8 = 0;
+const OFF: u8 = 1;
+const ON: u8 = 2;
+
+static STRICT: AtomicU8 = AtomicU8::new(UNSET);
+
+thread_local! {
+    static THREAD_STRICT: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Return true if strict mode is on for the current thread.
+pub fn is_strict() -> bool {
+    THREAD_STRICT
+        .with(Cell::get)
+        .unwrap_or_else(|| match STRICT.load(Ordering::Relaxed) {
+            UNSET => {
+                let from_env = matches!(
+                    env::var("IO_RESULT_OPTIONAL_STRICT").as_deref(),
+                    Ok("1") | Ok("true")
+                );
+                let value = if from_env { ON } else { OFF };
+                // If set_strict was called meanwhile, that wins.
+                match STRICT.compare_exchange(UNSET, value, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => from_env,
+                    Err(current) => current == ON,
+                }
+            }
+            current => current == ON,
+        })
+}
+
+/// Turn strict mode on or off for the whole process.
+///
+/// Threads with their own setting, from [`set_thread_strict`], are not
+/// affected.
+pub fn set_strict(strict: bool) {
+    STRICT.store(if strict { ON } else { OFF }, Ordering::Relaxed);
+}
+
+/// Turn strict mode on or off for the whole process, until the
+/// returned guard is dropped.
+pub fn strict_scope(strict: bool) -> StrictGuard {
+    let guard = StrictGuard {
+        previous: STRICT.load(Ordering::Relaxed),
+    };
+    set_strict(strict);
+    guard
+}
+
+/// Turn strict mode on or off for the current thread, or use the
+/// process-wide setting again with `None`.
+pub fn set_thread_strict(strict: Option<bool>) {
+    THREAD_STRICT.with(|cell| cell.set(strict));
+}
+
+/// Turn strict mode on or off for the current thread, until the
+/// returned guard is dropped.
+pub fn thread_strict_scope(strict: bool) -> ThreadStrictGuard {
+    ThreadStrictGuard {
+        previous: THREAD_STRICT.with(|cell| cell.replace(Some(strict))),
+        _not_send: PhantomData,
+    }
+}
+
+/// Restores the previous process-wide strict mode when dropped.
+///
+/// This is returned by [`strict_scope`].
+#[must_use = "strict mode is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct StrictGuard {
+    previous: u8,
+}
+
+impl Drop for StrictGuard {
+    fn drop(&mut self) {
+        STRICT.store(self.previous, Ordering::Relaxed);
+    }
+}
+
+/// Restores the previous strict mode of the current thread when
+/// dropped.
+///
+/// This is returned by [`thread_strict_scope`].
+#[must_use = "strict mode is restored when the guard is dropped"]
+#[derive(Debug)]
+pub struct ThreadStrictGuard {
+    previous: Option<bool>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ThreadStrictGuard {
+    fn drop(&mut self) {
+        set_thread_strict(self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_strict, thread_strict_scope};
+    use crate::{IoResultOptional, KindSet};
+    use std::io;
+
+    fn missing() -> io::Result<()> {
+        Err(io::ErrorKind::NotFound.into())
+    }
+
+    #[test]
+    fn thread_scope() {
+        {
+            let _strict = thread_strict_scope(true);
+            assert!(is_strict());
+            assert!(missing().optional().is_err());
+            let kinds = KindSet::new(&[io::ErrorKind::NotFound]);
+            assert!(missing().optional_kinds(&kinds).is_err());
+            {
+                let _lenient = thread_strict_scope(false);
+                assert!(missing().optional().unwrap().is_none());
+            }
+            assert!(missing().optional().is_err());
+        }
+        assert!(missing().optional().unwrap().is_none());
+    }
+}
@@ -0,0 +1,119 @@
+//! Optional entries in tar archives.
+//!
+//! This module is available with the `tar` feature.
+//!
+//! A tar archive is read sequentially, so each of these functions
+//! consumes the archive up to (and including) the entry found, or all
+//! of it if the entry does not exist.
+use ::tar::{Archive, Entry};
+use std::io::{self, Read};
+use std::path::{Component, Path};
+
+/// Find the entry with the given path in a tar archive.
+///
+/// If the archive does not contain an entry with the path, `Ok(None)`
+/// is returned.
+/// Errors reading the archive, such as a corrupt archive, are
+/// returned as-is.
+/// A leading `./` in entry paths is ignored.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::tar::find_entry;
+/// # use std::io::{self, Read};
+///
+/// # fn main() -> io::Result<()> {
+/// # let data = tar::Builder::new(Vec::new()).into_inner()?;
+/// let mut archive = tar::Archive::new(&data[..]);
+/// if let Some(mut entry) = find_entry(&mut archive, "package/Cargo.toml")? {
+///     let mut manifest = String::new();
+///     entry.read_to_string(&mut manifest)?;
+///     // Handle the manifest ...
+/// }
+/// # Ok(())
+/// # }
+/// ````
+pub fn find_entry<'a, R: Read, P: AsRef<Path>>(
+    archive: &'a mut Archive<R>,
+    path: P,
+) -> io::Result<Option<Entry<'a, R>>> {
+    let path = path.as_ref();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if same_path(&entry.path()?, path) {
+            return Ok(Some(entry));
+        }
+    }
+    Ok(None)
+}
+
+/// Extract the entry with the given path from a tar archive to `dst`.
+///
+/// Return true if the entry was extracted, and false if the archive
+/// does not contain an entry with the path.
+/// See [`find_entry`] for how the entry is found, and
+/// [`Entry::unpack`] for how it is extracted.
+pub fn extract_entry<R: Read, P: AsRef<Path>, Q: AsRef<Path>>(
+    archive: &mut Archive<R>,
+    path: P,
+    dst: Q,
+) -> io::Result<bool> {
+    match find_entry(archive, path)? {
+        Some(mut entry) => {
+            entry.unpack(dst)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    let relevant = |c: &Component| *c != Component::CurDir;
+    a.components()
+        .filter(relevant)
+        .eq(b.components().filter(relevant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_entry, find_entry};
+    use ::tar::{Archive, Builder, Header};
+    use std::io::{self, Read};
+
+    fn archive() -> Vec<u8> {
+        let mut builder = Builder::new(Vec::new());
+        let mut header = Header::new_gnu();
+        header.set_size(4);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "./pkg/data.txt", &b"data"[..])
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn existing_some() {
+        let data = archive();
+        let mut archive = Archive::new(&data[..]);
+        let mut entry = find_entry(&mut archive, "pkg/data.txt").unwrap().unwrap();
+        let mut content = String::new();
+        entry.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "data");
+    }
+
+    #[test]
+    fn non_existing_none() {
+        let data = archive();
+        let mut archive = Archive::new(&data[..]);
+        assert!(!extract_entry(&mut archive, "pkg/other.txt", "/nonexistent").unwrap());
+    }
+
+    #[test]
+    fn corrupt_is_error() {
+        let mut data = archive();
+        data[148] ^= 1; // Break the header checksum.
+        let mut archive = Archive::new(&data[..]);
+        let err = find_entry(&mut archive, "pkg/data.txt").err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+}
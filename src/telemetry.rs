@@ -0,0 +1,206 @@
+//! Timing of attempts to access things that may not exist.
+//!
+//! The helpers in [`fs`](crate::fs), and the other helpers for paths
+//! that may not exist, such as [`Tolerate::open`](crate::Tolerate::open),
+//! measure how long each attempt takes, and report it to a hook set
+//! with [`set_hook`].
+//! Attempts that take longer than the [slow threshold](set_slow_threshold)
+//! are marked as slow, which is useful for finding optional files on
+//! network file systems, where even not finding a file can take a
+//! long time.
+//!
+//! This module is available with the `telemetry` feature.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::telemetry::{set_hook, set_slow_threshold};
+//! use std::time::Duration;
+//!
+//! set_slow_threshold(Duration::from_millis(50));
+//! set_hook(|attempt| {
+//!     if attempt.slow {
+//!         eprintln!(
+//!             "warning: {} of {} took {:?}",
+//!             attempt.operation,
+//!             attempt.path.display(),
+//!             attempt.duration,
+//!         );
+//!     }
+//! });
+//! ````
+use crate::outcome::{LoadOutcome, Outcome};
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+type Hook = Arc<dyn Fn(&Attempt) + Send + Sync>;
+
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// The slow threshold in nanoseconds.
+static SLOW_THRESHOLD: AtomicU64 = AtomicU64::new(1_000_000_000);
+
+/// A timed attempt to access something that may not exist.
+#[derive(Debug)]
+pub struct Attempt<'a> {
+    /// The name of the public function making the attempt, such as
+    /// `"lines_optional"`, or of the type and method, such as
+    /// `"Tolerate::open"`.
+    pub operation: &'static str,
+    /// The path that was accessed.
+    ///
+    /// This is empty for attempts on an open file, such as
+    /// `sync_best_effort`.
+    pub path: &'a Path,
+    /// How the attempt ended.
+    pub outcome: Outcome,
+    /// When the attempt started.
+    pub timestamp: SystemTime,
+    /// How long the attempt took.
+    pub duration: Duration,
+    /// True if the attempt took longer than the slow threshold.
+    pub slow: bool,
+}
+
+impl Attempt<'_> {
+    /// Get a record of this attempt, that can be kept.
+    pub fn to_load_outcome(&self) -> LoadOutcome {
+        LoadOutcome {
+            path: self.path.into(),
+            operation: self.operation,
+            outcome: self.outcome.clone(),
+            timestamp: self.timestamp,
+            duration: self.duration,
+        }
+    }
+}
+
+/// Set the hook that is called after each attempt, replacing any
+/// previous hook.
+///
+/// The hook is called on the thread making the attempt, so it should
+/// be quick.
+/// It may use the helpers in [`fs`](crate::fs), and set or clear the
+/// hook, which takes effect from the next attempt.
+pub fn set_hook<F: Fn(&Attempt) + Send + Sync + 'static>(hook: F) {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(hook));
+}
+
+/// Remove the hook, if any.
+pub fn clear_hook() {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Set how long an attempt may take before it is considered slow.
+///
+/// The default is one second.
+pub fn set_slow_threshold(threshold: Duration) {
+    let nanos = u64::try_from(threshold.as_nanos()).unwrap_or(u64::MAX);
+    SLOW_THRESHOLD.store(nanos, Ordering::Relaxed);
+}
+
+/// Get how long an attempt may take before it is considered slow.
+pub fn slow_threshold() -> Duration {
+    Duration::from_nanos(SLOW_THRESHOLD.load(Ordering::Relaxed))
+}
+
+/// Make an attempt, and report it to the hook.
+///
+/// A successful attempt found what it looked for if `found` returns
+/// true for the value.
+pub(crate) fn timed<T>(
+    operation: &'static str,
+    path: &Path,
+    attempt: impl FnOnce() -> io::Result<T>,
+    found: impl FnOnce(&T) -> bool,
+) -> io::Result<T> {
+    let timestamp = SystemTime::now();
+    let start = Instant::now();
+    let result = attempt();
+    report(
+        operation,
+        path,
+        Outcome::of_found(&result, found),
+        timestamp,
+        start.elapsed(),
+    );
+    result
+}
+
+/// Report an attempt that started at `timestamp` to the hook.
+pub(crate) fn report(
+    operation: &'static str,
+    path: &Path,
+    outcome: Outcome,
+    timestamp: SystemTime,
+    duration: Duration,
+) {
+    // Not holding the lock while calling the hook lets the hook use
+    // the crate, including setting a new hook.
+    let hook = HOOK.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(hook) = hook {
+        hook(&Attempt {
+            operation,
+            path,
+            outcome,
+            timestamp,
+            duration,
+            slow: duration > slow_threshold(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear_hook, set_hook};
+    use crate::fs::{
+        canonicalize_optional, create_with_parents, lines_optional, truncate_if_exists,
+    };
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn reports_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let hook_seen = seen.clone();
+        set_hook(move |attempt| {
+            if attempt.path.starts_with(&root) {
+                let record = attempt.to_load_outcome();
+                let found = record.outcome.is_found();
+                hook_seen
+                    .lock()
+                    .unwrap()
+                    .push((record.operation, record.path, found));
+            }
+        });
+        let missing = dir.path().join("missing");
+        lines_optional(&missing).unwrap();
+        canonicalize_optional(dir.path()).unwrap();
+        truncate_if_exists(&missing).unwrap();
+        let created = dir.path().join("sub/created");
+        create_with_parents(&created).unwrap();
+        let expected: Vec<(&str, PathBuf, bool)> = vec![
+            ("lines_optional", missing.clone(), false),
+            ("canonicalize_optional", Path::new(dir.path()).into(), true),
+            ("truncate_if_exists", missing, false),
+            ("create_with_parents", created, true),
+        ];
+        assert_eq!(*seen.lock().unwrap(), expected);
+
+        // A hook can use the crate without deadlocking.
+        let nested = dir.path().join("nested");
+        set_hook(move |attempt| {
+            if attempt.path != nested {
+                lines_optional(&nested).unwrap();
+                clear_hook();
+            }
+        });
+        lines_optional(dir.path().join("outer")).unwrap();
+        clear_hook();
+    }
+}
@@ -0,0 +1,191 @@
+//! Falling back to a temporary file when a file can not be created.
+//!
+//! This module is available with the `tempfile` feature.
+use crate::fs::timed_found;
+use crate::KindSet;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Kinds of errors meaning that a file can not be created where it was
+/// wanted, so a temporary file is used instead.
+///
+/// These are that the directory does not exist (or is a file), that
+/// permission is denied, and that the file system is read-only.
+pub const FALLBACK_KINDS: KindSet = KindSet::new(&[
+    ErrorKind::NotFound,
+    ErrorKind::NotADirectory,
+    ErrorKind::PermissionDenied,
+    ErrorKind::ReadOnlyFilesystem,
+]);
+
+/// Create a file at `path`, or a temporary file if that is not
+/// possible.
+///
+/// The file is created, or truncated if it exists, and opened for
+/// writing, like with [`File::create`].
+/// If that fails with an error in [`FALLBACK_KINDS`], a new file is
+/// created in the temporary directory instead, with a name based on
+/// the name of `path`.
+/// The temporary file is not removed, so it can be used after the
+/// program is done.
+/// Any other error is returned.
+/// See [`OpenOrTempOptions`] to choose which errors to fall back on.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::tempfile::{open_or_temp, Location};
+/// use std::io::Write;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let mut report = open_or_temp("/nosuch/dir/report.txt")?;
+/// report.file.write_all(b"All done.\n")?;
+/// if let Location::Temp { path, reason } = &report.location {
+///     eprintln!("Could not write the report ({}), wrote {} instead", reason, path.display());
+/// }
+/// # std::fs::remove_file(report.location.path())?;
+/// # Ok(())
+/// # }
+/// ````
+pub fn open_or_temp<P: AsRef<Path>>(path: P) -> io::Result<OrTemp> {
+    OpenOrTempOptions::new().open(path)
+}
+
+/// Options for [`open_or_temp`].
+#[derive(Clone, Debug)]
+pub struct OpenOrTempOptions {
+    fallback: KindSet,
+}
+
+impl OpenOrTempOptions {
+    /// Create the default options, falling back for the errors in
+    /// [`FALLBACK_KINDS`].
+    pub fn new() -> Self {
+        OpenOrTempOptions {
+            fallback: FALLBACK_KINDS,
+        }
+    }
+
+    /// Set the kinds of errors to fall back to a temporary file for.
+    pub fn fallback_kinds(mut self, kinds: KindSet) -> Self {
+        self.fallback = kinds;
+        self
+    }
+
+    /// Create a file at `path`, or a temporary file if that fails with
+    /// an error to fall back for.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<OrTemp> {
+        let path = path.as_ref();
+        timed_found(
+            "open_or_temp",
+            path,
+            || self.create(path),
+            |created| !created.location.is_temp(),
+        )
+    }
+
+    fn create(&self, path: &Path) -> io::Result<OrTemp> {
+        match File::create(path) {
+            Ok(file) => Ok(OrTemp {
+                file,
+                location: Location::Preferred(path.into()),
+            }),
+            Err(reason) if self.fallback.matches(&reason) => {
+                let (file, temp) = create_temp(path)?;
+                Ok(OrTemp {
+                    file,
+                    location: Location::Temp { path: temp, reason },
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Default for OpenOrTempOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Create a temporary file named like `path`, keeping its extension
+/// last, that is not removed when it is closed.
+fn create_temp(path: &Path) -> io::Result<(File, PathBuf)> {
+    let mut prefix = path.file_stem().map_or_else(OsString::new, Into::into);
+    prefix.push(".");
+    let mut suffix = OsString::new();
+    if let Some(ext) = path.extension() {
+        suffix.push(".");
+        suffix.push(ext);
+    }
+    let temp = ::tempfile::Builder::new()
+        .prefix(&prefix)
+        .suffix(&suffix)
+        .tempfile()?;
+    temp.keep().map_err(|e| e.error)
+}
+
+/// A file created by [`open_or_temp`], with where it was created.
+#[derive(Debug)]
+pub struct OrTemp {
+    /// The file, opened for writing.
+    pub file: File,
+    /// Where the file was created.
+    pub location: Location,
+}
+
+/// Where [`open_or_temp`] created a file.
+#[derive(Debug)]
+pub enum Location {
+    /// The file was created at the given path.
+    Preferred(PathBuf),
+    /// The file could not be created at the given path, so a temporary
+    /// file was created instead.
+    Temp {
+        /// The path of the temporary file.
+        path: PathBuf,
+        /// The error creating the file at the given path.
+        reason: io::Error,
+    },
+}
+
+impl Location {
+    /// The path of the file, wherever it was created.
+    pub fn path(&self) -> &Path {
+        match self {
+            Location::Preferred(path) => path,
+            Location::Temp { path, .. } => path,
+        }
+    }
+
+    /// Return true if a temporary file was used.
+    pub fn is_temp(&self) -> bool {
+        matches!(self, Location::Temp { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open_or_temp, OpenOrTempOptions};
+    use crate::KindSet;
+    use std::fs;
+
+    #[test]
+    fn fallback() {
+        let dir = ::tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let created = open_or_temp(&path).unwrap();
+        assert!(!created.location.is_temp());
+        assert_eq!(created.location.path(), path);
+        let missing = dir.path().join("nosuch/out.txt");
+        let created = open_or_temp(&missing).unwrap();
+        let temp = created.location.path().to_owned();
+        assert!(created.location.is_temp());
+        assert!(temp.to_str().unwrap().ends_with(".txt"));
+        fs::remove_file(temp).unwrap();
+        let strict = OpenOrTempOptions::new().fallback_kinds(KindSet::new(&[]));
+        assert!(strict.open(&missing).is_err());
+    }
+}
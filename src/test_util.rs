@@ -0,0 +1,102 @@
+//! Helpers for testing how errors are handled.
+//!
+//! This module is available with the `test-util` feature.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::test_util::kind_matrix;
+//! use io_result_optional::IoResultOptional;
+//! use std::io;
+//!
+//! for error in kind_matrix() {
+//!     let kind = error.kind();
+//!     let result: io::Result<()> = Err(error);
+//!     assert_eq!(result.optional().is_ok(), kind == io::ErrorKind::NotFound);
+//! }
+//! ````
+use crate::kinds::ALL_KINDS;
+use std::io::{self, ErrorKind};
+
+/// Raw os error codes, with the kinds std gives them.
+#[cfg(unix)]
+pub(crate) const OS_ERRORS: &[(i32, ErrorKind)] = &[
+    (1, ErrorKind::PermissionDenied),    // EPERM
+    (2, ErrorKind::NotFound),            // ENOENT
+    (4, ErrorKind::Interrupted),         // EINTR
+    (13, ErrorKind::PermissionDenied),   // EACCES
+    (16, ErrorKind::ResourceBusy),       // EBUSY
+    (17, ErrorKind::AlreadyExists),      // EEXIST
+    (20, ErrorKind::NotADirectory),      // ENOTDIR
+    (21, ErrorKind::IsADirectory),       // EISDIR
+    (22, ErrorKind::InvalidInput),       // EINVAL
+    (28, ErrorKind::StorageFull),        // ENOSPC
+    (30, ErrorKind::ReadOnlyFilesystem), // EROFS
+    (32, ErrorKind::BrokenPipe),         // EPIPE
+];
+
+/// Raw os error codes, with the kinds std gives them.
+#[cfg(windows)]
+pub(crate) const OS_ERRORS: &[(i32, ErrorKind)] = &[
+    (2, ErrorKind::NotFound),         // ERROR_FILE_NOT_FOUND
+    (3, ErrorKind::NotFound),         // ERROR_PATH_NOT_FOUND
+    (5, ErrorKind::PermissionDenied), // ERROR_ACCESS_DENIED
+    (80, ErrorKind::AlreadyExists),   // ERROR_FILE_EXISTS
+    (87, ErrorKind::InvalidInput),    // ERROR_INVALID_PARAMETER
+    (112, ErrorKind::StorageFull),    // ERROR_DISK_FULL
+    (183, ErrorKind::AlreadyExists),  // ERROR_ALREADY_EXISTS
+];
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) const OS_ERRORS: &[(i32, ErrorKind)] = &[];
+
+/// Get one error of each stable [`io::ErrorKind`], including `Other`.
+///
+/// Each error has a message naming its kind, so that a failing
+/// assertion tells which one it was.
+pub fn kind_matrix() -> impl Iterator<Item = io::Error> {
+    ALL_KINDS
+        .iter()
+        .chain(Some(ErrorKind::Other))
+        .map(|kind| io::Error::new(kind, format!("test error of kind {:?}", kind)))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Get the errors of [`kind_matrix`], followed by some raw os errors
+/// of the platform.
+///
+/// A raw os error gets its kind from std, like a real error from the
+/// operating system, so this can check that code handles both kinds
+/// of errors the same way.
+pub fn kind_matrix_with_os_errors() -> impl Iterator<Item = io::Error> {
+    kind_matrix().chain(
+        OS_ERRORS
+            .iter()
+            .map(|(code, _)| io::Error::from_raw_os_error(*code)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{kind_matrix, kind_matrix_with_os_errors, OS_ERRORS};
+    use crate::kinds::MISSING_KINDS;
+    use crate::IoResultOptional;
+    use std::io;
+
+    #[test]
+    fn os_error_kinds() {
+        for (code, kind) in OS_ERRORS {
+            assert_eq!(io::Error::from_raw_os_error(*code).kind(), *kind);
+        }
+    }
+
+    #[test]
+    fn optional_kinds_exhaustive() {
+        assert!(kind_matrix().count() > OS_ERRORS.len());
+        for error in kind_matrix_with_os_errors() {
+            let missing = MISSING_KINDS.matches(&error);
+            let result: io::Result<()> = Err(error);
+            assert_eq!(result.optional_kinds(&MISSING_KINDS).is_ok(), missing);
+        }
+    }
+}
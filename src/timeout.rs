@@ -0,0 +1,77 @@
+//! Bounding the time of blocking operations.
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Run a blocking operation, but give up after `timeout`.
+///
+/// The operation is run on a new thread.
+/// If it does not finish in time, a `TimedOut` error is returned.
+/// The thread can not be stopped, so the operation keeps running in
+/// the background, and its result is dropped when it finishes.
+///
+/// This is useful for operations that may block for a long time, or
+/// forever, such as accessing a file on a hung network file system.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::{with_timeout, IoResultOptional};
+/// use std::fs;
+/// use std::time::Duration;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let meta = with_timeout(Duration::from_secs(5), || fs::metadata("/mnt/share/flag"))
+///     .optional()?;
+/// # Ok(())
+/// # }
+/// ````
+pub fn with_timeout<T, F>(timeout: Duration, operation: F) -> io::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel(1);
+    thread::Builder::new()
+        .name("io-timeout".into())
+        .spawn(move || {
+            // If the receiver has given up, the result is not needed.
+            let _ = tx.send(operation());
+        })?;
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("operation timed out after {:?}", timeout),
+        )),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(io::Error::other("operation panicked")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::with_timeout;
+    use crate::IoResultOptional;
+    use std::io;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn timeout() {
+        assert_eq!(
+            with_timeout(Duration::from_secs(10), || Ok(17)).unwrap(),
+            17
+        );
+        let slow = with_timeout(Duration::from_millis(10), || {
+            thread::sleep(Duration::from_secs(1));
+            Ok(())
+        });
+        assert_eq!(slow.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        let slow = with_timeout(Duration::from_millis(10), || {
+            thread::sleep(Duration::from_secs(1));
+            Ok(())
+        });
+        assert!(slow.optional_timed_out().unwrap().is_none());
+    }
+}
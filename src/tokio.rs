@@ -0,0 +1,284 @@
+//! Opening optional files with tokio.
+//!
+//! This module is available with the `tokio` feature.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::tokio::open_optional;
+//! # use std::io;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> io::Result<()> {
+//! if let Some(file) = open_optional(".app.rc").await? {
+//!     // Read the configuration ...
+//! }
+//! # Ok(())
+//! # }
+//! ````
+use crate::{IoOption, IoResultOptional};
+use ::tokio::fs::File;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Open a file that may not exist.
+///
+/// See [`OpenOptional`].
+pub fn open_optional<P: AsRef<Path>>(path: P) -> OpenOptional {
+    OpenOptional {
+        open: open("open_optional", path, IoResultOptional::optional),
+    }
+}
+
+type Open = Pin<Box<dyn Future<Output = IoOption<File>> + Send>>;
+
+/// Open `path`, with the result converted by `optional`.
+///
+/// The open is timed with the `telemetry` feature, and errors
+/// tolerated by `optional` are recorded with the path with the `audit`
+/// feature, like in the [`fs`](crate::fs) helpers.
+fn open<P: AsRef<Path>>(
+    operation: &'static str,
+    path: P,
+    optional: fn(io::Result<File>) -> IoOption<File>,
+) -> Open {
+    let path = path.as_ref().to_owned();
+    Box::pin(async move {
+        #[cfg(feature = "telemetry")]
+        let (timestamp, start) = (std::time::SystemTime::now(), std::time::Instant::now());
+        let result = File::open(&path).await;
+        #[cfg(feature = "audit")]
+        let result = crate::audit::with_path(&path, || optional(result));
+        #[cfg(not(feature = "audit"))]
+        let result = optional(result);
+        #[cfg(feature = "telemetry")]
+        crate::telemetry::report(
+            operation,
+            &path,
+            crate::outcome::Outcome::of(&result),
+            timestamp,
+            start.elapsed(),
+        );
+        #[cfg(not(feature = "telemetry"))]
+        let _ = operation;
+        result
+    })
+}
+
+/// What [`open_first`] does with an error other than `NotFound`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnError {
+    /// Return the error, unless a path given before it is opened, or
+    /// another path is opened while waiting for those.
+    Fail,
+    /// Treat the path like one that does not exist.
+    Skip,
+}
+
+/// Open the first of several paths that exists.
+///
+/// All paths are tried concurrently, which is much faster than trying
+/// one at a time on a slow network file system.
+/// The result is the index and file of the first path that could be
+/// opened, where the path given first wins if several are opened at
+/// the same time.
+/// A path that does not exist is skipped, and other errors are handled
+/// as given by `on_error`.
+/// If no path could be opened, the result is `Ok(None)`.
+///
+/// The opens that are still going on when this is done are cancelled,
+/// see [`OpenOptional`].
+///
+/// # Examples
+/// ````
+/// use io_result_optional::tokio::{open_first, OnError};
+/// # use std::io;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> io::Result<()> {
+/// let paths = [".app.rc", "/net/home/app.rc", "/etc/app.rc"];
+/// if let Some((i, file)) = open_first(&paths, OnError::Fail).await? {
+///     println!("Using configuration from {}", paths[i]);
+/// }
+/// # Ok(())
+/// # }
+/// ````
+pub async fn open_first<I>(paths: I, on_error: OnError) -> IoOption<(usize, File)>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+{
+    let opens = paths
+        .into_iter()
+        .map(|p| open("open_first", p, IoResultOptional::optional))
+        .collect();
+    race(opens, on_error).await
+}
+
+/// Get the first of `opens` that gives a file, see [`open_first`].
+async fn race(opens: Vec<Open>, on_error: OnError) -> IoOption<(usize, File)> {
+    let mut opens = opens.into_iter().map(Some).collect::<Vec<_>>();
+    let mut error: Option<(usize, io::Error)> = None;
+    poll_fn(|cx| {
+        for (i, slot) in opens.iter_mut().enumerate() {
+            if let Some(Poll::Ready(result)) = slot.as_mut().map(|open| open.as_mut().poll(cx)) {
+                *slot = None;
+                match result {
+                    Ok(Some(file)) => return Poll::Ready(Ok(Some((i, file)))),
+                    Ok(None) => (),
+                    Err(_) if on_error == OnError::Skip => (),
+                    Err(e) => match error {
+                        Some((first, _)) if first < i => (),
+                        _ => error = Some((i, e)),
+                    },
+                }
+            }
+        }
+        // An error only ends the race when no path before it may still
+        // be opened.
+        match error {
+            Some((i, _)) if opens[..i].iter().all(Option::is_none) => {
+                Poll::Ready(Err(error.take().unwrap().1))
+            }
+            _ if opens.iter().any(Option::is_some) => Poll::Pending,
+            _ => Poll::Ready(Ok(None)),
+        }
+    })
+    .await
+}
+
+/// A future opening a file that may not exist.
+///
+/// This resolves to `Ok(Some(file))` if the file could be opened,
+/// `Ok(None)` if it does not exist, and `Err(error)` if something else
+/// went wrong, just like [`optional`](IoResultOptional::optional).
+///
+/// # Cancel safety
+///
+/// This future is cancel safe, so it can be used as a branch in
+/// `tokio::select!`.
+/// If it is dropped before it is done, the caller never gets a file,
+/// and the future can just be created again.
+/// The underlying open runs on the blocking thread pool of tokio, and
+/// is left to finish there, like a cancelled
+/// [`File::open`](::tokio::fs::File::open).
+///
+/// # Examples
+/// ````
+/// use io_result_optional::tokio::open_optional;
+/// use std::time::Duration;
+/// use tokio::time::sleep;
+/// # use std::io;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> io::Result<()> {
+/// for _ in 0..3 {
+///     tokio::select! {
+///         file = open_optional(".app.rc") => {
+///             if let Some(file) = file? {
+///                 // Reload the configuration ...
+///             }
+///         }
+///         _ = sleep(Duration::from_millis(10)) => {}
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[must_use = "futures do nothing unless polled"]
+pub struct OpenOptional {
+    open: Open,
+}
+
+impl Future for OpenOptional {
+    type Output = IoOption<File>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.open.as_mut().poll(cx)
+    }
+}
+
+impl std::fmt::Debug for OpenOptional {
+    fn fmt(&self, out: &mut std::fmt::Formatter) -> std::fmt::Result {
+        out.debug_struct("OpenOptional").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open_first, open_optional, race, OnError, Open};
+    use ::tokio::fs::File;
+    use ::tokio::time::sleep;
+    use std::future::{pending, poll_fn, ready, Future};
+    use std::io;
+    use std::pin::Pin;
+    use std::task::Poll;
+    use std::time::Duration;
+
+    #[::tokio::test]
+    async fn open() {
+        assert!(open_optional("nosuch.file").await.unwrap().is_none());
+        assert!(open_optional("Cargo.toml").await.unwrap().is_some());
+    }
+
+    #[::tokio::test]
+    async fn first() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let paths = [file.join("sub"), dir.path().join("nosuch"), file];
+        let (i, _) = open_first(&paths, OnError::Skip).await.unwrap().unwrap();
+        assert_eq!(i, 2);
+        let error = open_first(&paths[..2], OnError::Fail).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotADirectory);
+        assert!(open_first(&paths[..2], OnError::Skip)
+            .await
+            .unwrap()
+            .is_none());
+        let _strict = crate::strict::thread_strict_scope(true);
+        let error = open_first(&paths[1..2], OnError::Fail).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[::tokio::test]
+    async fn first_waits_for_earlier_paths() {
+        let opened: Open = Box::pin(async {
+            sleep(Duration::from_millis(50)).await;
+            File::open("Cargo.toml").await.map(Some)
+        });
+        let missing: Open = Box::pin(async {
+            sleep(Duration::from_millis(50)).await;
+            Ok(None)
+        });
+        let failed = || -> Open { Box::pin(ready(Err(io::Error::other("failed")))) };
+        let (i, _) = race(vec![opened, failed()], OnError::Fail)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(i, 0);
+        let error = race(vec![missing, failed()], OnError::Fail).await;
+        assert_eq!(error.unwrap_err().to_string(), "failed");
+    }
+
+    #[::tokio::test]
+    async fn cancel() {
+        let mut cancelled = 0;
+        for _ in 0..10 {
+            // Start the open, and drop it while it is still going on.
+            let mut open = open_optional("Cargo.toml");
+            let polled = poll_fn(|cx| Poll::Ready(Pin::new(&mut open).poll(cx))).await;
+            if polled.is_pending() {
+                cancelled += 1;
+            }
+            drop(open);
+        }
+        assert!(cancelled > 0);
+        let file = ::tokio::select! {
+            file = open_optional("Cargo.toml") => file.unwrap(),
+            _ = pending::<()>() => unreachable!(),
+        };
+        assert!(file.is_some());
+    }
+}
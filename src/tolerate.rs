@@ -0,0 +1,252 @@
+//! A reusable policy for which errors to tolerate.
+use crate::fs::timed;
+use crate::kinds::{ParseKindError, NOT_FOUND};
+use crate::{tolerate_at, IoOption, KindSet};
+use std::env::{self, VarError};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
+use std::panic::Location;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A policy for which errors mean that something is optional.
+///
+/// This is like [`optional_kinds`](crate::IoResultOptional::optional_kinds),
+/// but the policy can be built once, cloned, and used everywhere in a
+/// project, and it can call a hook for each error it tolerates.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::Tolerate;
+/// use std::fs;
+/// use std::io::{self, ErrorKind};
+///
+/// # fn main() -> io::Result<()> {
+/// let policy = Tolerate::missing()
+///     .and(ErrorKind::PermissionDenied)
+///     .with_hook(|e| eprintln!("Ignoring: {}", e));
+///
+/// let config = policy.apply(fs::read_to_string(".app.rc"))?;
+/// let theme = policy.read_to_string("theme.css")?;
+/// # Ok(())
+/// # }
+/// ````
+///
+/// With the `serde` feature, a policy is serialized like its
+/// [`KindSet`], without the hook.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Tolerate {
+    kinds: KindSet,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hook: Option<Hook>,
+}
+
+type Hook = Arc<dyn Fn(&io::Error) + Send + Sync>;
+
+impl Tolerate {
+    /// A policy tolerating no errors at all.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// A policy tolerating `NotFound`, like
+    /// [`optional`](crate::IoResultOptional::optional).
+    pub fn missing() -> Self {
+        Tolerate::kinds(NOT_FOUND)
+    }
+
+    /// A policy tolerating the given kinds.
+    pub fn kinds(kinds: KindSet) -> Self {
+        Tolerate { kinds, hook: None }
+    }
+
+    /// Parse a policy from a comma-separated list of kinds and groups,
+    /// such as `"notfound,permission-denied,timeout"`.
+    ///
+    /// See [`KindSet::from_str`] for the names.
+    pub fn parse(value: &str) -> Result<Self, ParseKindError> {
+        value.parse().map(Tolerate::kinds)
+    }
+
+    /// Parse a policy from an environment variable.
+    ///
+    /// If the variable is not set, an error of kind `NotFound` is
+    /// returned, so `.optional()` can be used to tell that case apart.
+    /// A value that can not be parsed gives an `InvalidInput` error.
+    ///
+    /// # Examples
+    /// ````
+    /// use io_result_optional::{IoResultOptional, Tolerate};
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let policy = Tolerate::from_env("APP_TOLERATE")
+    ///     .optional()?
+    ///     .unwrap_or_else(Tolerate::missing);
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn from_env<K: AsRef<OsStr>>(key: K) -> io::Result<Self> {
+        let key = key.as_ref();
+        let value = env::var(key).map_err(|e| match e {
+            VarError::NotPresent => io::Error::new(
+                ErrorKind::NotFound,
+                format!("environment variable {} is not set", key.to_string_lossy()),
+            ),
+            VarError::NotUnicode(_) => io::Error::new(ErrorKind::InvalidData, e),
+        })?;
+        Tolerate::parse(&value).map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))
+    }
+
+    /// Also tolerate `kind`.
+    pub fn and(mut self, kind: ErrorKind) -> Self {
+        self.kinds = self.kinds.with(kind);
+        self
+    }
+
+    /// Also tolerate the given kinds.
+    pub fn and_kinds(mut self, kinds: &KindSet) -> Self {
+        self.kinds = self.kinds.union(kinds);
+        self
+    }
+
+    /// Call `hook` with each error that is tolerated.
+    ///
+    /// This replaces any hook set before.
+    pub fn with_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&io::Error) + Send + Sync + 'static,
+    {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Return true if `error` is of a kind that this policy tolerates.
+    pub fn tolerates(&self, error: &io::Error) -> bool {
+        self.kinds.matches(error)
+    }
+
+    /// Apply the policy to a result.
+    ///
+    /// An error that is tolerated is given to the hook and converted to
+    /// `Ok(None)`.
+    /// Any other error is returned as-is, and `Ok(value)` is converted
+    /// to `Ok(Some(value))`.
+    #[track_caller]
+    pub fn apply<T>(&self, result: io::Result<T>) -> IoOption<T> {
+        self.apply_at(result, Location::caller())
+    }
+
+    fn apply_at<T>(
+        &self,
+        result: io::Result<T>,
+        caller: &'static Location<'static>,
+    ) -> IoOption<T> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if self.tolerates(e) && tolerate_at(Some(e), caller) => {
+                if let Some(hook) = &self.hook {
+                    hook(e);
+                }
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Open a file for reading, applying this policy.
+    #[track_caller]
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> IoOption<File> {
+        let (path, caller) = (path.as_ref(), Location::caller());
+        timed("Tolerate::open", path, || {
+            self.apply_at(File::open(path), caller)
+        })
+    }
+
+    /// Read a file, applying this policy.
+    #[track_caller]
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> IoOption<Vec<u8>> {
+        let (path, caller) = (path.as_ref(), Location::caller());
+        timed("Tolerate::read", path, || {
+            self.apply_at(fs::read(path), caller)
+        })
+    }
+
+    /// Read a text file, applying this policy.
+    #[track_caller]
+    pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> IoOption<String> {
+        let (path, caller) = (path.as_ref(), Location::caller());
+        timed("Tolerate::read_to_string", path, || {
+            self.apply_at(fs::read_to_string(path), caller)
+        })
+    }
+}
+
+impl FromStr for Tolerate {
+    type Err = ParseKindError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Tolerate::parse(value)
+    }
+}
+
+impl fmt::Debug for Tolerate {
+    fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
+        out.debug_struct("Tolerate")
+            .field("kinds", &self.kinds)
+            .field("hook", &self.hook.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tolerate;
+    use std::io::{self, ErrorKind};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn apply() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        let policy = Tolerate::missing()
+            .and(ErrorKind::PermissionDenied)
+            .with_hook(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        let denied: io::Result<()> = Err(ErrorKind::PermissionDenied.into());
+        assert!(policy.clone().apply(denied).unwrap().is_none());
+        let busy: io::Result<()> = Err(ErrorKind::ResourceBusy.into());
+        assert!(policy.apply(busy).is_err());
+        assert!(policy.read("nosuch.file").unwrap().is_none());
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+        assert!(Tolerate::none().read("nosuch.file").is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let policy = Tolerate::parse("notfound,permission-denied").unwrap();
+        assert!(policy.tolerates(&ErrorKind::PermissionDenied.into()));
+        assert!(!policy.tolerates(&ErrorKind::TimedOut.into()));
+        assert!(Tolerate::parse("notfound,bogus").is_err());
+        let unset = Tolerate::from_env("IO_RESULT_OPTIONAL_NOSUCH_VAR").unwrap_err();
+        assert_eq!(unset.kind(), ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let policy: Tolerate = serde_json::from_str(r#"["missing", "timeout"]"#).unwrap();
+        assert!(policy.tolerates(&ErrorKind::NotADirectory.into()));
+        assert_eq!(
+            serde_json::to_string(&Tolerate::missing()).unwrap(),
+            r#"["NotFound"]"#
+        );
+    }
+}
@@ -0,0 +1,560 @@
+//! Watching a file that may not exist.
+//!
+//! A watched file may appear, be modified, and be removed, any number
+//! of times, and each of those is reported as an [`Event`].
+//!
+//! [`PollWatcher`] and [`watch_polling`] work anywhere, by checking
+//! the file at regular intervals.
+//! With the `notify` feature, `watch_optional` uses the native file
+//! system notifications of the platform instead.
+//!
+//! For a directory that may not exist, [`snapshot`] and [`diff`] tell
+//! which entries have been added, removed or modified, without
+//! watching it.
+use crate::fs::timed;
+use crate::kinds::NOT_FOUND;
+use crate::probe;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// Something that happened to a watched file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The file did not exist, but now it does.
+    Appeared,
+    /// The file existed and still does, but its content has changed.
+    Modified,
+    /// The file existed, but now it does not.
+    Removed,
+}
+
+/// The state of a watched file, enough to tell if it has changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    /// Get the current stamp of `path`, or `None` if it does not exist.
+    fn of(path: &Path) -> io::Result<Option<Stamp>> {
+        Ok(probe(fs::metadata(path), &NOT_FOUND)?.map(|meta| Stamp {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        }))
+    }
+
+    /// Get the event for going from `old` to `new`, if any.
+    fn event(old: Option<Stamp>, new: Option<Stamp>) -> Option<Event> {
+        match (old, new) {
+            (None, Some(_)) => Some(Event::Appeared),
+            (Some(_), None) => Some(Event::Removed),
+            (Some(old), Some(new)) if old != new => Some(Event::Modified),
+            _ => None,
+        }
+    }
+}
+
+/// Watch a file that may not exist by checking it when asked to.
+///
+/// A file is considered modified when its modification time or size
+/// has changed.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::watch::{Event, PollWatcher};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let mut watcher = PollWatcher::new("app.toml")?;
+/// // Later, e.g. once every turn of a main loop:
+/// if let Some(event) = watcher.poll()? {
+///     println!("The configuration file was {:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Debug)]
+pub struct PollWatcher {
+    path: PathBuf,
+    stamp: Option<Stamp>,
+}
+
+impl PollWatcher {
+    /// Start watching `path`.
+    ///
+    /// No event is reported for the initial state of the file.
+    pub fn new<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        let stamp = Stamp::of(&path)?;
+        Ok(PollWatcher { path, stamp })
+    }
+
+    /// Check the file, and return what happened to it since the last
+    /// check, if anything.
+    ///
+    /// If the file changed more than once since the last check, only
+    /// the total change is reported, possibly as none at all.
+    pub fn poll(&mut self) -> io::Result<Option<Event>> {
+        let stamp = Stamp::of(&self.path)?;
+        let event = Stamp::event(self.stamp, stamp);
+        self.stamp = stamp;
+        Ok(event)
+    }
+}
+
+/// Watch a file that may not exist by checking it at regular intervals
+/// in a background thread.
+///
+/// Call `callback` with each event, or error checking the file,
+/// until the returned handle is dropped.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::watch::watch_polling;
+/// use std::time::Duration;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let _watch = watch_polling("app.toml", Duration::from_secs(2), |event| match event {
+///     Ok(event) => println!("The configuration file was {:?}", event),
+///     Err(e) => eprintln!("Failed to check configuration file: {}", e),
+/// })?;
+/// # Ok(())
+/// # }
+/// ````
+pub fn watch_polling<P, F>(path: P, interval: Duration, mut callback: F) -> io::Result<PollHandle>
+where
+    P: Into<PathBuf>,
+    F: FnMut(io::Result<Event>) + Send + 'static,
+{
+    let mut watcher = PollWatcher::new(path)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        thread::spawn(move || loop {
+            thread::park_timeout(interval);
+            if stop.load(Ordering::Acquire) {
+                break;
+            }
+            if let Some(event) = watcher.poll().transpose() {
+                callback(event);
+            }
+        })
+    };
+    Ok(PollHandle {
+        stop,
+        thread: Some(thread),
+    })
+}
+
+/// A handle for a watch started by [`watch_polling`].
+///
+/// The watch is stopped when this is dropped.
+#[derive(Debug)]
+pub struct PollHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for PollHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Take a snapshot of the names, sizes and modification times of the
+/// entries in a directory that may not exist.
+///
+/// A missing directory gives an empty snapshot, and an entry that is
+/// removed while the snapshot is taken is left out of it.
+/// Compare two snapshots with [`diff`] to find out what has changed.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::watch::{diff, snapshot};
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let before = snapshot("conf.d")?;
+/// // Later:
+/// let after = snapshot("conf.d")?;
+/// for name in diff(&before, &after).added {
+///     println!("New configuration {:?}", name);
+/// }
+/// # Ok(())
+/// # }
+/// ````
+pub fn snapshot<P: AsRef<Path>>(path: P) -> io::Result<Snapshot> {
+    let path = path.as_ref();
+    Ok(timed("snapshot", path, || read_snapshot(path))?.unwrap_or_default())
+}
+
+/// Take a snapshot of a directory, or `None` if it does not exist.
+fn read_snapshot(path: &Path) -> io::Result<Option<Snapshot>> {
+    let dir = match probe(fs::read_dir(path), &NOT_FOUND)? {
+        Some(dir) => dir,
+        None => return Ok(None),
+    };
+    let mut entries = BTreeMap::new();
+    for entry in dir {
+        let entry = entry?;
+        if let Some(stamp) = Stamp::of(&entry.path())? {
+            entries.insert(entry.file_name(), stamp);
+        }
+    }
+    Ok(Some(Snapshot { entries }))
+}
+
+/// The entries of a directory at some time, taken by [`snapshot`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    entries: BTreeMap<OsString, Stamp>,
+}
+
+impl Snapshot {
+    /// The names of the entries, in order.
+    pub fn names(&self) -> impl Iterator<Item = &OsStr> {
+        self.entries.keys().map(OsString::as_os_str)
+    }
+
+    /// Return true if there is an entry named `name`.
+    pub fn contains<N: AsRef<OsStr>>(&self, name: N) -> bool {
+        self.entries.contains_key(name.as_ref())
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if there are no entries, such as when the directory
+    /// does not exist.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Find what has changed from the `old` snapshot to the `new` one.
+///
+/// An entry is considered modified when its modification time or size
+/// has changed.
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Changes {
+    let mut changes = Changes::default();
+    for (name, stamp) in &old.entries {
+        match new.entries.get(name) {
+            None => changes.removed.insert(name.clone()),
+            Some(new) if new != stamp => changes.modified.insert(name.clone()),
+            Some(_) => false,
+        };
+    }
+    for name in new.entries.keys() {
+        if !old.entries.contains_key(name) {
+            changes.added.insert(name.clone());
+        }
+    }
+    changes
+}
+
+/// The changes between two snapshots, found by [`diff`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// Names of entries that are new.
+    pub added: BTreeSet<OsString>,
+    /// Names of entries that are gone.
+    pub removed: BTreeSet<OsString>,
+    /// Names of entries that are modified.
+    pub modified: BTreeSet<OsString>,
+}
+
+impl Changes {
+    /// Return true if nothing has changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+#[cfg(feature = "notify")]
+pub use self::native::{watch_optional, OptionalWatcher};
+
+#[cfg(feature = "notify")]
+mod native {
+    use super::{Event, Stamp};
+    use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::sync::{mpsc, Arc, Mutex, Weak};
+    use std::thread;
+    use std::time::Duration;
+
+    /// Watch a file that may not exist, using native notifications.
+    ///
+    /// The returned watcher reports an [`Event`] each time the file
+    /// appears, is modified, or is removed.
+    /// It does not matter if the file, or even the directory it is
+    /// in, exists when the watch is started.
+    /// No event is reported for the initial state of the file.
+    ///
+    /// This is available with the `notify` feature.
+    ///
+    /// # Examples
+    /// ````no_run
+    /// use io_result_optional::watch::{watch_optional, Event};
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// for event in watch_optional("/etc/app/override.toml")? {
+    ///     match event? {
+    ///         Event::Appeared | Event::Modified => println!("Reload the override"),
+    ///         Event::Removed => println!("Drop the override"),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn watch_optional<P: AsRef<Path>>(path: P) -> io::Result<OptionalWatcher> {
+        let path = path.as_ref().to_path_buf();
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let _ = raw_tx.send(event);
+        })
+        .map_err(into_io)?;
+        let watcher = Arc::new(Mutex::new(watcher));
+        let (tx, events) = mpsc::channel();
+        let mut state = State {
+            watched: watch_ancestor(&watcher, &path, None)?,
+            stamp: Stamp::of(&path)?,
+            path,
+        };
+        let weak = Arc::downgrade(&watcher);
+        thread::spawn(move || {
+            // Ends when the watcher is dropped, as it owns raw_tx.
+            for raw in raw_rx {
+                let event = raw.map_err(into_io).and_then(|_| state.update(&weak));
+                if let Some(event) = event.transpose() {
+                    if tx.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(OptionalWatcher {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// A watcher for a file that may not exist.
+    ///
+    /// This is created by [`watch_optional`].
+    /// Iterating over it blocks until the next event.
+    /// The watch is stopped when it is dropped.
+    #[derive(Debug)]
+    pub struct OptionalWatcher {
+        _watcher: Arc<Mutex<RecommendedWatcher>>,
+        events: mpsc::Receiver<io::Result<Event>>,
+    }
+
+    impl OptionalWatcher {
+        /// Wait up to `timeout` for the next event.
+        ///
+        /// Return `None` if there was no event in time.
+        pub fn recv_timeout(&self, timeout: Duration) -> Option<io::Result<Event>> {
+            self.events.recv_timeout(timeout).ok()
+        }
+
+        /// Get the next event, if there is one already.
+        pub fn try_recv(&self) -> Option<io::Result<Event>> {
+            self.events.try_recv().ok()
+        }
+    }
+
+    impl Iterator for OptionalWatcher {
+        type Item = io::Result<Event>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.events.recv().ok()
+        }
+    }
+
+    struct State {
+        path: PathBuf,
+        watched: PathBuf,
+        stamp: Option<Stamp>,
+    }
+
+    impl State {
+        fn update(
+            &mut self,
+            watcher: &Weak<Mutex<RecommendedWatcher>>,
+        ) -> io::Result<Option<Event>> {
+            if let Some(watcher) = watcher.upgrade() {
+                self.watched = watch_ancestor(&watcher, &self.path, Some(&self.watched))?;
+            }
+            let stamp = Stamp::of(&self.path)?;
+            let event = Stamp::event(self.stamp, stamp);
+            self.stamp = stamp;
+            Ok(event)
+        }
+    }
+
+    /// Watch the nearest existing ancestor of `path`, unless it is
+    /// already watched.
+    fn watch_ancestor(
+        watcher: &Mutex<RecommendedWatcher>,
+        path: &Path,
+        watched: Option<&Path>,
+    ) -> io::Result<PathBuf> {
+        let dir = path
+            .ancestors()
+            .skip(1)
+            .map(|dir| {
+                if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir
+                }
+            })
+            .find(|dir| dir.is_dir())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no directory to watch"))?;
+        if Some(dir) != watched {
+            let mut watcher = watcher.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(old) = watched {
+                // The old directory may be gone, and then its watch is too.
+                let _ = watcher.unwatch(old);
+            }
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(into_io)?;
+        }
+        Ok(dir.to_path_buf())
+    }
+
+    fn into_io(error: notify::Error) -> io::Error {
+        match error.kind {
+            notify::ErrorKind::Io(e) => e,
+            notify::ErrorKind::PathNotFound => io::ErrorKind::NotFound.into(),
+            _ => io::Error::other(error),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::super::Event;
+        use super::watch_optional;
+        use std::fs;
+        use std::time::Duration;
+
+        #[test]
+        fn appear_modify_remove() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("sub").join("file");
+            let watcher = watch_optional(&path).unwrap();
+            let next = || {
+                watcher
+                    .recv_timeout(Duration::from_secs(5))
+                    .expect("an event")
+                    .unwrap()
+            };
+            fs::create_dir(dir.path().join("sub")).unwrap();
+            fs::write(&path, "one").unwrap();
+            assert_eq!(next(), Event::Appeared);
+            while watcher.recv_timeout(Duration::from_millis(100)).is_some() {}
+            fs::write(&path, "three").unwrap();
+            assert_eq!(next(), Event::Modified);
+            while watcher.recv_timeout(Duration::from_millis(100)).is_some() {}
+            fs::remove_file(&path).unwrap();
+            assert_eq!(next(), Event::Removed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, snapshot, watch_polling, Event, PollWatcher, Stamp};
+    use std::fs;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn events() {
+        let a = Some(Stamp {
+            modified: None,
+            len: 1,
+        });
+        let b = Some(Stamp {
+            modified: None,
+            len: 2,
+        });
+        assert_eq!(Stamp::event(None, None), None);
+        assert_eq!(Stamp::event(None, a), Some(Event::Appeared));
+        assert_eq!(Stamp::event(a, a), None);
+        assert_eq!(Stamp::event(a, b), Some(Event::Modified));
+        assert_eq!(Stamp::event(b, None), Some(Event::Removed));
+    }
+
+    #[test]
+    fn poll() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        let mut watcher = PollWatcher::new(&path).unwrap();
+        assert_eq!(watcher.poll().unwrap(), None);
+        fs::write(&path, "one").unwrap();
+        assert_eq!(watcher.poll().unwrap(), Some(Event::Appeared));
+        assert_eq!(watcher.poll().unwrap(), None);
+        fs::write(&path, "three").unwrap();
+        assert_eq!(watcher.poll().unwrap(), Some(Event::Modified));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.poll().unwrap(), Some(Event::Removed));
+    }
+
+    #[test]
+    fn snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("conf.d");
+        let empty = snapshot(&conf).unwrap();
+        assert!(empty.is_empty());
+        fs::create_dir(&conf).unwrap();
+        fs::write(conf.join("a"), "a").unwrap();
+        fs::write(conf.join("b"), "b").unwrap();
+        let first = snapshot(&conf).unwrap();
+        assert_eq!(first.names().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(diff(&empty, &first).added.len(), 2);
+
+        fs::write(conf.join("b"), "bigger").unwrap();
+        fs::remove_file(conf.join("a")).unwrap();
+        fs::write(conf.join("c"), "c").unwrap();
+        let changes = diff(&first, &snapshot(&conf).unwrap());
+        assert_eq!(changes.added.into_iter().collect::<Vec<_>>(), ["c"]);
+        assert_eq!(changes.removed.into_iter().collect::<Vec<_>>(), ["a"]);
+        assert_eq!(changes.modified.into_iter().collect::<Vec<_>>(), ["b"]);
+        assert!(diff(&first, &first).is_empty());
+    }
+
+    #[test]
+    fn polling_thread() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        let (tx, rx) = mpsc::channel();
+        let watch = watch_polling(&path, Duration::from_millis(10), move |event| {
+            let _ = tx.send(event.unwrap());
+        })
+        .unwrap();
+        fs::write(&path, "one").unwrap();
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event, Event::Appeared);
+        drop(watch);
+        assert!(rx.recv().is_err());
+    }
+}
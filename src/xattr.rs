@@ -0,0 +1,126 @@
+//! Reading extended attributes of files that may not exist.
+//!
+//! This module is available on unix with the `unix-xattr` feature.
+//!
+//! Both a missing file and a missing attribute are `None`.
+//! A file system that does not support extended attributes is an
+//! error by default, but can be considered missing as well, see
+//! [`XattrOptions::unsupported_is_missing`].
+use crate::IoResultOptional;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::Path;
+
+/// Get the extended attribute `name` of the file at `path`.
+///
+/// Return `Ok(None)` if the file does not exist, or does not have
+/// the attribute.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::xattr::get_xattr_optional;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// if let Some(tags) = get_xattr_optional("photo.jpg", "user.xdg.tags")? {
+///     // The photo exists and has tags ...
+/// }
+/// # Ok(())
+/// # }
+/// ````
+pub fn get_xattr_optional<P: AsRef<Path>, N: AsRef<OsStr>>(
+    path: P,
+    name: N,
+) -> io::Result<Option<Vec<u8>>> {
+    XattrOptions::new().get(path, name)
+}
+
+/// List the names of the extended attributes of the file at `path`.
+///
+/// Return `Ok(None)` if the file does not exist.
+pub fn list_xattrs_optional<P: AsRef<Path>>(path: P) -> io::Result<Option<Vec<OsString>>> {
+    XattrOptions::new().list(path)
+}
+
+/// Options for how to read extended attributes.
+///
+/// # Examples
+/// ````
+/// use io_result_optional::xattr::XattrOptions;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// let value = XattrOptions::new()
+///     .unsupported_is_missing(true)
+///     .get("backup.tar", "user.backup.checksum")?;
+/// # Ok(())
+/// # }
+/// ````
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XattrOptions {
+    unsupported_is_missing: bool,
+}
+
+impl XattrOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set if a file system that does not support extended
+    /// attributes should be considered to have no attributes.
+    /// The default is false, making it an error.
+    pub fn unsupported_is_missing(mut self, value: bool) -> Self {
+        self.unsupported_is_missing = value;
+        self
+    }
+
+    /// Get the extended attribute `name` of the file at `path`.
+    ///
+    /// See [`get_xattr_optional`].
+    pub fn get<P: AsRef<Path>, N: AsRef<OsStr>>(
+        &self,
+        path: P,
+        name: N,
+    ) -> io::Result<Option<Vec<u8>>> {
+        match ::xattr::get(path, name) {
+            Err(ref e) if self.is_unsupported(e) => Ok(None),
+            result => Ok(result.optional()?.flatten()),
+        }
+    }
+
+    /// List the names of the extended attributes of the file at `path`.
+    ///
+    /// See [`list_xattrs_optional`].
+    pub fn list<P: AsRef<Path>>(&self, path: P) -> io::Result<Option<Vec<OsString>>> {
+        match ::xattr::list(path) {
+            Err(ref e) if self.is_unsupported(e) => Ok(Some(Vec::new())),
+            result => Ok(result.optional()?.map(Iterator::collect)),
+        }
+    }
+
+    fn is_unsupported(&self, error: &io::Error) -> bool {
+        self.unsupported_is_missing && error.kind() == io::ErrorKind::Unsupported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_xattr_optional, list_xattrs_optional, XattrOptions};
+    use std::path::Path;
+
+    #[test]
+    fn non_existing_file_none() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("nosuch.file");
+        assert!(get_xattr_optional(&path, "user.test").unwrap().is_none());
+        assert!(list_xattrs_optional(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn non_existing_attribute_none() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let options = XattrOptions::new().unsupported_is_missing(true);
+        assert!(options.get(&path, "user.nosuch").unwrap().is_none());
+        assert!(options.list(&path).unwrap().is_some());
+    }
+}
@@ -0,0 +1,78 @@
+//! Optional zip archives, and optional entries in zip archives.
+//!
+//! This module is available with the `zip` feature.
+//! With it, a [`ZipError::FileNotFound`] is [missing](MaybeMissing),
+//! so looking up an entry that may not exist in an archive is just
+//! like opening a file that may not exist.
+//!
+//! # Examples
+//! ````
+//! use io_result_optional::zip::open_zip_optional;
+//! use io_result_optional::ResultOptional;
+//! # use zip::result::ZipResult;
+//!
+//! # fn main() -> ZipResult<()> {
+//! if let Some(mut archive) = open_zip_optional("assets.zip")? {
+//!     if let Some(entry) = archive.by_name("icon.png").optional()? {
+//!         // The archive contains the icon, so handle it ...
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ````
+use crate::{IoResultOptional, MaybeMissing};
+use ::zip::result::{ZipError, ZipResult};
+use ::zip::ZipArchive;
+use std::fs::File;
+use std::path::Path;
+
+impl MaybeMissing for ZipError {
+    fn is_missing(&self) -> bool {
+        matches!(self, ZipError::FileNotFound)
+    }
+}
+
+/// Open a zip archive that may not exist.
+///
+/// If the file does not exist, `Ok(None)` is returned.
+/// If it exists but is not a valid zip archive, that is an error.
+pub fn open_zip_optional<P: AsRef<Path>>(path: P) -> ZipResult<Option<ZipArchive<File>>> {
+    File::open(path)
+        .optional()?
+        .map(ZipArchive::new)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::open_zip_optional;
+    use crate::ResultOptional;
+    use ::zip::write::{SimpleFileOptions, ZipWriter};
+    use ::zip::ZipArchive;
+    use std::io::{Cursor, Write};
+    use std::path::Path;
+
+    #[test]
+    fn non_existing_none() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("nosuch.zip");
+        assert!(open_zip_optional(path).unwrap().is_none());
+    }
+
+    #[test]
+    fn not_a_zip_is_error() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        assert!(open_zip_optional(path).is_err());
+    }
+
+    #[test]
+    fn entries() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            SimpleFileOptions::default().compression_method(::zip::CompressionMethod::Stored);
+        writer.start_file("present.txt", options).unwrap();
+        writer.write_all(b"here").unwrap();
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert!(archive.by_name("present.txt").optional().unwrap().is_some());
+        assert!(archive.by_name("absent.txt").optional().unwrap().is_none());
+    }
+}